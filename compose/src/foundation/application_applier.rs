@@ -26,4 +26,16 @@ impl Applier<Box<dyn Any>> for ApplicationApplier {
 
     fn clear(&mut self) {
     }
+
+    fn insert_top_down(&self, _index: usize, _instance: Box<dyn Any>) {
+    }
+
+    fn insert_bottom_up(&self, _index: usize, _instance: Box<dyn Any>) {
+    }
+
+    fn remove(&self, _index: usize, _count: usize) {
+    }
+
+    fn r#move(&self, _from: usize, _to: usize, _count: usize) {
+    }
 }
\ No newline at end of file
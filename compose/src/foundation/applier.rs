@@ -1,8 +1,14 @@
-use auto_delegate::{delegate, Delegate};
+use auto_delegate::delegate;
 use std::any::Any;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use crate::foundation::compose_node::ComposedNode;
 
+/// Applies the node changes of a composition to a tree of `N`. The changes are applied relative to the
+/// current node, which is moved through the tree by `down` and `up`.
 #[delegate]
-pub(crate) trait Applier<N> where N: Any + 'static {
+pub trait Applier<N> where N: Any + 'static {
     fn get_current(&self) -> &N;
     fn on_begin_changes(&self) {}
     fn on_end_changes(&self) {}
@@ -10,54 +16,123 @@ pub(crate) trait Applier<N> where N: Any + 'static {
     fn up(&mut self);
     fn clear(&mut self);
 
-    fn insert_top_down(&self, index: usize, instance: N) {
-        unimplemented!()
-    }
+    /// insert `instance` into the current node while the tree is built from the root down, an applier
+    /// which builds the tree from the leaves up in `insert_bottom_up` leaves this empty
+    fn insert_top_down(&self, index: usize, instance: N);
 
-    fn insert_bottom_up(&self, index: usize, instance: N) { unimplemented!() }
+    /// `instance` may still be a child of another node when the movable content which emitted it was
+    /// moved, it is taken out of that node first
+    fn insert_bottom_up(&self, index: usize, instance: N);
 
-    fn remove(&self, index: usize, count: usize) {
-        unimplemented!()
-    }
+    fn remove(&self, index: usize, count: usize);
 
-    fn r#move(&self, from: usize, to: usize, count: usize) {
-        unimplemented!()
-    }
+    /// move `count` children starting at `from` in front of the child at `to`, `to` counts the moved
+    /// children as if they were still in place
+    fn r#move(&self, from: usize, to: usize, count: usize);
 }
 
-#[derive(Delegate)]
-pub(crate) struct AbstractApplier<T> {
+/// keeps the current node for an `Applier`, which forwards `get_current`, `down`, `up` and `clear` to it
+/// and applies the tree specific changes itself
+pub struct AbstractApplier<T> {
     pub(crate) root: T,
     current: T,
     stack: Vec<T>,
 }
 
 impl<T> AbstractApplier<T> where T: Clone {
-    pub(crate) fn new(root: T) -> Self {
+    pub fn new(root: T) -> Self {
         Self {
             current: root.clone(),
             root,
             stack: vec![],
         }
     }
-}
 
-impl<T> Applier<T> for AbstractApplier<T> where T: Any + Clone {
-    fn get_current(&self) -> &T {
+    pub fn root(&self) -> &T {
+        &self.root
+    }
+
+    pub fn get_current(&self) -> &T {
         &self.current
     }
 
-    fn down(&mut self, mut node: T) {
+    pub fn down(&mut self, mut node: T) {
         std::mem::swap(&mut node, &mut self.current);
         self.stack.push(node);
     }
 
-    fn up(&mut self) {
+    pub fn up(&mut self) {
         self.current = self.stack.pop().unwrap();
     }
 
-    fn clear(&mut self) {
+    pub fn clear(&mut self) {
         self.stack.clear();
         self.current = self.root.clone();
     }
-}
\ No newline at end of file
+}
+
+/// hands the nodes kept by the composer to an applier of a tree of `T`
+pub(crate) struct NodeApplier<T, A> {
+    applier: A,
+    nodes: AbstractApplier<ComposedNode>,
+    _marker: PhantomData<T>,
+}
+
+impl<T, A> NodeApplier<T, A> where T: 'static, A: Applier<Rc<RefCell<T>>> {
+    pub(crate) fn new(root: ComposedNode, applier: A) -> Self {
+        Self {
+            applier,
+            nodes: AbstractApplier::new(root),
+            _marker: PhantomData,
+        }
+    }
+
+    fn node(node: &ComposedNode) -> Rc<RefCell<T>> {
+        node.downcast::<T>().expect("node emitted into the composition of another tree")
+    }
+}
+
+impl<T, A> Applier<ComposedNode> for NodeApplier<T, A> where T: 'static, A: Applier<Rc<RefCell<T>>> {
+    fn get_current(&self) -> &ComposedNode {
+        self.nodes.get_current()
+    }
+
+    fn on_begin_changes(&self) {
+        self.applier.on_begin_changes()
+    }
+
+    fn on_end_changes(&self) {
+        self.applier.on_end_changes()
+    }
+
+    fn down(&mut self, node: ComposedNode) {
+        self.applier.down(Self::node(&node));
+        self.nodes.down(node);
+    }
+
+    fn up(&mut self) {
+        self.applier.up();
+        self.nodes.up();
+    }
+
+    fn clear(&mut self) {
+        self.applier.clear();
+        self.nodes.clear();
+    }
+
+    fn insert_top_down(&self, index: usize, instance: ComposedNode) {
+        self.applier.insert_top_down(index, Self::node(&instance))
+    }
+
+    fn insert_bottom_up(&self, index: usize, instance: ComposedNode) {
+        self.applier.insert_bottom_up(index, Self::node(&instance))
+    }
+
+    fn remove(&self, index: usize, count: usize) {
+        self.applier.remove(index, count)
+    }
+
+    fn r#move(&self, from: usize, to: usize, count: usize) {
+        self.applier.r#move(from, to, count)
+    }
+}
@@ -28,7 +28,7 @@ pub(crate) fn root_measure_policy() -> MultiChildrenMeasurePolicy {
                     let mut max_width = 0;
                     let mut max_height = 0;
 
-                    let mut placeables = measurables
+                    let placeables = measurables
                         .into_iter()
                         .map(|measurable| {
                             let (measure_result, placeable) = measurable.measure(constraint);
@@ -44,8 +44,8 @@ pub(crate) fn root_measure_policy() -> MultiChildrenMeasurePolicy {
                          constraint.constrain_height(max_height)),
                         move |place_scope: &dyn PlacementScope| {
                             placeables
-                                .iter_mut()
-                                .for_each(|placeable| place_scope.place_relative(&placeable, 0, 0));
+                                .iter()
+                                .for_each(|placeable| place_scope.place_relative(placeable, 0, 0));
                         },
                     )
                 }
@@ -5,15 +5,21 @@ use std::rc::{Rc, Weak};
 
 use crate::foundation::bridge::root_measure_policy::root_measure_policy;
 use crate::foundation::canvas::Canvas;
-use crate::foundation::composer::Composer;
+use crate::foundation::composition::Composition;
+use crate::foundation::composition_local::CompositionLocalProvider;
 use crate::foundation::constraint::Constraints;
 use crate::foundation::geometry::{Density, IntOffset, IntRect, Offset};
 use crate::foundation::layout_direction::LayoutDirection;
 use crate::foundation::layout_node::LayoutNode;
 use crate::foundation::measure_and_layout_delegate::MeasureAndLayoutDelegate;
 use crate::foundation::node::{GesstureOwner, Owner};
+use crate::foundation::saveable::saveable_state_registry::{SaveableStateRegistry, LOCAL_SAVEABLE_STATE_REGISTRY};
 use crate::foundation::ui::input::pointer_input_event_processor::{PointerInputEventProcessor, PositionCalculator};
+use crate::foundation::ui::compose_scene::{CoroutineTasks, LOCAL_COROUTINE_TASKS};
+use crate::foundation::ui::frame_stats::{record_phase, FramePhase};
 use crate::foundation::ui::input::process_result::ProcessResult;
+use crate::foundation::ui::platform::composition_locals::{LOCAL_DENSITY, LOCAL_LAYOUT_DIRECTION, LOCAL_VIEW_CONFIGURATION};
+use crate::foundation::ui::platform::view_configuration::ViewConfiguration;
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
 
 struct SkiaBaseOwnerPositionCalculator;
@@ -29,7 +35,12 @@ impl PositionCalculator for SkiaBaseOwnerPositionCalculator {
 
 pub struct SkiaBaseOwner {
     bound: IntRect,
+    density: Density,
+    layout_direction: LayoutDirection,
+    saveable_state_registry: Option<Rc<SaveableStateRegistry>>,
+    coroutine_tasks: Option<CoroutineTasks>,
     root: Rc<RefCell<LayoutNode>>,
+    composition: Composition,
     measure_and_layout_delegate: MeasureAndLayoutDelegate,
     pointer_input_event_processor: PointerInputEventProcessor,
     position_calculator: Rc<dyn PositionCalculator>,
@@ -37,7 +48,7 @@ pub struct SkiaBaseOwner {
 
 impl Drop for SkiaBaseOwner {
     fn drop(&mut self) {
-        Composer::detach_root_layout_node();
+        self.composition.dispose();
         self.detach()
     }
 }
@@ -50,7 +61,12 @@ impl SkiaBaseOwner {
 
         let mut result = SkiaBaseOwner {
             bound,
+            density: Density::default(),
+            layout_direction: LayoutDirection::default(),
+            saveable_state_registry: None,
+            coroutine_tasks: None,
             root: root.clone(),
+            composition: Composition::new(root.clone()),
             measure_and_layout_delegate,
             pointer_input_event_processor: PointerInputEventProcessor::new(root.clone()),
             position_calculator: Rc::new(SkiaBaseOwnerPositionCalculator),
@@ -60,16 +76,16 @@ impl SkiaBaseOwner {
             .measure_and_layout_delegate
             .update_root_measure_policy(root_measure_policy());
 
-        if !Composer::attach_root_layout_node(root.clone()) {
-            panic!("unable to create multiple compose view in single thread");
-        }
-
         let result = result.wrap_with_rc_refcell();
 
         Self::init(&result);
         result
     }
 
+    pub fn composition(&self) -> &Composition {
+        &self.composition
+    }
+
     pub fn update_bound(&mut self, bound: IntRect) {
         self.bound = bound;
     }
@@ -89,12 +105,42 @@ impl SkiaBaseOwner {
         self.root.borrow_mut().detach();
     }
 
-    pub fn set_content(&self, content: impl Fn()) {
-        Composer::do_set_content(content);
+    /// takes effect for the content set afterwards
+    pub fn set_density(&mut self, density: Density) {
+        self.density = density;
+    }
+
+    pub fn set_layout_direction(&mut self, layout_direction: LayoutDirection) {
+        self.layout_direction = layout_direction;
+    }
+
+    pub fn set_saveable_state_registry(&mut self, registry: Option<Rc<SaveableStateRegistry>>) {
+        self.saveable_state_registry = registry;
+    }
+
+    /// the tasks the effects of the content are launched onto, set by the scene the owner is attached to
+    pub(crate) fn set_coroutine_tasks(&mut self, coroutine_tasks: Option<CoroutineTasks>) {
+        self.coroutine_tasks = coroutine_tasks;
+    }
+
+    pub fn set_content(&self, content: impl Fn() + 'static) {
+        let density = self.density;
+        let layout_direction = self.layout_direction;
+        let saveable_state_registry = self.saveable_state_registry.clone();
+        let coroutine_tasks = self.coroutine_tasks.clone();
+        self.composition.set_content(move || {
+            CompositionLocalProvider(vec![
+                LOCAL_DENSITY.provides(density),
+                LOCAL_LAYOUT_DIRECTION.provides(layout_direction),
+                LOCAL_VIEW_CONFIGURATION.provides(ViewConfiguration::default()),
+                LOCAL_SAVEABLE_STATE_REGISTRY.provides(saveable_state_registry.clone()),
+                LOCAL_COROUTINE_TASKS.provides(coroutine_tasks.clone()),
+            ], &content);
+        });
     }
 
     pub fn no_insert_set_content(&self, content: impl Fn()) {
-        Composer::do_compose_validate_structure(content);
+        self.composition.compose_validate_structure(content);
     }
 
     pub fn dispatch_measure(&mut self, width: usize, height: usize) {
@@ -110,7 +156,7 @@ impl SkiaBaseOwner {
 
     pub fn dispatch_draw(&mut self, canvas: &mut dyn Canvas) {
         let draw_delegate = self.measure_and_layout_delegate.root.borrow().layout_node_draw_delegate.clone();
-        draw_delegate.borrow_mut().draw(canvas);
+        record_phase(FramePhase::Draw, || draw_delegate.borrow_mut().draw(canvas));
     }
 }
 
@@ -120,11 +166,11 @@ impl Owner for SkiaBaseOwner {
     }
 
     fn get_density(&self) -> Density {
-        todo!()
+        self.density
     }
 
     fn get_layout_direction(&self) -> LayoutDirection {
-        todo!()
+        self.layout_direction
     }
 
     fn on_request_relayout(&mut self, layout_node: Weak<RefCell<LayoutNode>>) {
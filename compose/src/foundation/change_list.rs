@@ -0,0 +1,75 @@
+use crate::foundation::composer_impl::ApplierInType;
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+use crate::foundation::applier::Applier;
+use crate::foundation::composer_impl::{Change, ChangeType};
+use crate::foundation::remember_manager::{RememberEventDispatcher, RememberManager};
+use crate::foundation::utils::box_wrapper::WrapWithBox;
+
+/// the changes recorded by a composition, waiting to be applied to its nodes
+pub(crate) struct ChangeList {
+    applier: Box<dyn Applier<ApplierInType>>,
+    changes: Vec<Change>,
+    deferred_changes: Vec<Change>,
+}
+
+impl ChangeList {
+    pub(crate) fn new(applier: impl Applier<ApplierInType> + 'static) -> Self {
+        Self {
+            applier: applier.wrap_with_box(),
+            changes: Vec::new(),
+            deferred_changes: vec![],
+        }
+    }
+
+    pub(crate) fn record(&mut self, action: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
+        self.changes.push(Change {
+            change: Box::new(action),
+            change_type: ChangeType::Changes,
+        });
+    }
+
+    /// count of the changes recorded since they were applied last
+    pub(crate) fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// drop the changes recorded after the first `len` ones
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.changes.truncate(len);
+    }
+
+    pub(crate) fn record_deferred_change(&mut self, deferred_change: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
+        self.deferred_changes.push(Change {
+            change: Box::new(deferred_change),
+            change_type: ChangeType::DeferredChange,
+        });
+    }
+
+    /// returns the remember events recorded by the changes, they are dispatched by the caller
+    pub(crate) fn apply_changes(&mut self) -> RememberEventDispatcher {
+        let mut changes = Vec::<Change>::new();
+        std::mem::swap(&mut self.changes, &mut changes);
+
+        self.applier.on_begin_changes();
+
+        let mut remember_dispatcher = RememberEventDispatcher::new();
+        changes.into_iter().for_each(|change| {
+            (change.change)(self.applier.deref_mut(), &mut remember_dispatcher);
+        });
+
+        self.applier.on_end_changes();
+        remember_dispatcher
+    }
+
+    pub(crate) fn apply_deferred_changes(&mut self) -> RememberEventDispatcher {
+        let mut deferred_changes = Vec::<Change>::new();
+        std::mem::swap(&mut self.deferred_changes, &mut deferred_changes);
+
+        let mut remember_dispatcher = RememberEventDispatcher::new();
+        deferred_changes.into_iter().for_each(|change| {
+            (change.change)(self.applier.deref_mut(), &mut remember_dispatcher);
+        });
+        remember_dispatcher
+    }
+}
\ No newline at end of file
@@ -0,0 +1,130 @@
+use futures::{Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch};
+
+use crate::foundation::mutable_state::MutableState;
+use crate::foundation::produce_state::produce_state;
+
+/// The value of a watch channel as a state, it is updated whenever a new value is sent. The
+/// receiver is collected while the call site stays in the composition, receivers passed by later
+/// compositions are dropped. Each call site keeps a state of its own.
+pub trait WatchCollectAsState<T> {
+    #[track_caller]
+    fn collect_as_state(self) -> MutableState<T>;
+}
+
+impl<T: Clone + 'static> WatchCollectAsState<T> for watch::Receiver<T> {
+    #[track_caller]
+    fn collect_as_state(mut self) -> MutableState<T> {
+        let initial = self.borrow_and_update().clone();
+        produce_state(initial, &(), move |state| async move {
+            while self.changed().await.is_ok() {
+                let value = self.borrow_and_update().clone();
+                state.set(value);
+            }
+        })
+    }
+}
+
+/// The last value received from a broadcast channel as a state, it holds `initial` until the first
+/// value arrives. Values missed because the receiver lagged behind are skipped.
+pub trait BroadcastCollectAsState<T> {
+    #[track_caller]
+    fn collect_as_state(self, initial: T) -> MutableState<T>;
+}
+
+impl<T: Clone + 'static> BroadcastCollectAsState<T> for broadcast::Receiver<T> {
+    #[track_caller]
+    fn collect_as_state(mut self, initial: T) -> MutableState<T> {
+        produce_state(initial, &(), move |state| async move {
+            loop {
+                match self.recv().await {
+                    Ok(value) => state.set(value),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+}
+
+/// The last item of a stream as a state, it holds `initial` until the first item arrives.
+pub trait StreamCollectAsState<T> {
+    #[track_caller]
+    fn collect_as_state(self, initial: T) -> MutableState<T>;
+}
+
+impl<T: 'static, S: Stream<Item=T> + 'static> StreamCollectAsState<T> for S {
+    #[track_caller]
+    fn collect_as_state(self, initial: T) -> MutableState<T> {
+        produce_state(initial, &(), move |state| async move {
+            let mut stream = Box::pin(self);
+            while let Some(value) = stream.next().await {
+                state.set(value);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use tokio::sync::watch;
+
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::collect_as_state::WatchCollectAsState;
+    use crate::foundation::geometry::{Density, IntRect};
+    use crate::foundation::ui::compose_scene::ComposeScene;
+
+    thread_local! {
+        static SHOW_FIRST: Cell<bool> = Cell::new(true);
+        static RECEIVERS: RefCell<Option<(watch::Receiver<&'static str>, watch::Receiver<&'static str>)>> = RefCell::new(None);
+        static COLLECTED: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+    }
+
+    fn content() {
+        let (first, second) = RECEIVERS.with(|receivers| receivers.borrow().clone().unwrap());
+        let mut collected = vec![];
+        if SHOW_FIRST.with(Cell::get) {
+            collected.push(first.collect_as_state().get());
+        }
+        collected.push(second.collect_as_state().get());
+        COLLECTED.with(|current| *current.borrow_mut() = collected);
+    }
+
+    fn collected() -> Vec<&'static str> {
+        COLLECTED.with(|collected| collected.borrow().clone())
+    }
+
+    #[test]
+    fn sibling_collectors_keep_their_own_state() {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut scene = ComposeScene::new(runtime, Density::default(), Box::new(|| {}));
+        let (first_sender, first) = watch::channel("first");
+        let (second_sender, second) = watch::channel("second");
+        RECEIVERS.with(|receivers| *receivers.borrow_mut() = Some((first, second)));
+
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        scene.attach(owner.clone());
+        let owner = owner.borrow();
+        let composition = owner.composition();
+        owner.set_content(content);
+        composition.apply_changes();
+        scene.dispatch_coroutines();
+        assert_eq!(collected(), vec!["first", "second"]);
+
+        SHOW_FIRST.with(|show| show.set(false));
+        owner.set_content(content);
+        composition.apply_changes();
+        scene.dispatch_coroutines();
+        assert_eq!(collected(), vec!["second"]);
+
+        first_sender.send("first changed").unwrap();
+        second_sender.send("second changed").unwrap();
+        scene.dispatch_coroutines();
+        owner.set_content(content);
+        composition.apply_changes();
+        assert_eq!(collected(), vec!["second changed"]);
+    }
+}
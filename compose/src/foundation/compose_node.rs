@@ -1,12 +1,65 @@
-use compose_macro::Composable;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::foundation::compose_node_lifecycle_callback::ComposeNodeLifecycleCallback;
 use crate::foundation::composer::Composer;
 use crate::foundation::updater::Updater;
-use crate as compose;
 
-pub(crate) fn ComposeNode(update: impl FnOnce(&mut Updater) + 'static, mut content: impl FnMut()) {
+/// a node emitted by `ComposeNode`, the composer keeps it without knowing the tree it belongs to
+#[derive(Clone)]
+pub(crate) struct ComposedNode {
+    node: Rc<dyn Any>,
+    lifecycle: Rc<RefCell<dyn ComposeNodeLifecycleCallback>>,
+}
+
+impl ComposedNode {
+    pub(crate) fn new<T: ComposeNodeLifecycleCallback + 'static>(node: Rc<RefCell<T>>) -> Self {
+        Self {
+            lifecycle: node.clone(),
+            node,
+        }
+    }
+
+    /// the node as a node of a tree of `T`, `None` if it belongs to another tree
+    pub(crate) fn downcast<T: 'static>(&self) -> Option<Rc<RefCell<T>>> {
+        self.node.clone().downcast::<RefCell<T>>().ok()
+    }
+
+    pub(crate) fn lifecycle(&self) -> Rc<RefCell<dyn ComposeNodeLifecycleCallback>> {
+        self.lifecycle.clone()
+    }
+}
+
+/// Emit a node into the tree of the composition, the node is created by `factory` and inserted by the
+/// `Applier` of the composition. `update` sets up the node when it is created, and updates it when the
+/// values it depends on change. The nodes emitted by `content` become the children of the node.
+pub fn ComposeNode<T>(factory: impl FnOnce() -> Rc<RefCell<T>> + 'static,
+                      update: impl FnOnce(&mut Updater<T>) + 'static,
+                      mut content: impl FnMut()) where T: ComposeNodeLifecycleCallback + 'static {
     Composer::start_node();
     if Composer::inserting() {
-        Composer::create_node();
+        Composer::create_node(move || ComposedNode::new(factory()));
+    } else {
+        Composer::use_node();
+    }
+    Composer::static_dispatch_mut(move |composer| {
+        update(&mut Updater::new(composer));
+    });
+
+    content();
+    Composer::end_node();
+}
+
+
+/// like `ComposeNode`, the node is kept and updated for the new content when the reusable content
+/// around it is reused
+pub fn ReusableComposeNode<T>(factory: impl FnOnce() -> Rc<RefCell<T>> + 'static,
+                              update: impl FnOnce(&mut Updater<T>) + 'static,
+                              mut content: impl FnMut()) where T: ComposeNodeLifecycleCallback + 'static {
+    Composer::start_reusable_node();
+    if Composer::inserting() {
+        Composer::create_node(move || ComposedNode::new(factory()));
     } else {
         Composer::use_node();
     }
@@ -1,5 +1,9 @@
-pub(crate) trait ComposeNodeLifecycleCallback {
-    fn on_reuse(&mut self);
-    fn on_deactivate(&mut self);
-    fn on_release(&mut self);
-}
\ No newline at end of file
+/// the lifecycle of a node emitted by `ComposeNode`, driven by the composition owning the node
+pub trait ComposeNodeLifecycleCallback {
+    /// the node is kept for reusable content composed in place of the content which emitted it
+    fn on_reuse(&mut self) {}
+    /// the reusable content which emitted the node left the composition, it may be reused later
+    fn on_deactivate(&mut self) {}
+    /// the node left the composition for good
+    fn on_release(&mut self) {}
+}
@@ -1,13 +1,22 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::{Rc, Weak}};
 use std::ops::{Deref, DerefMut};
 use crate::foundation::applier::Applier;
 
 use std::any::Any;
-use crate::foundation::composer_impl::{ComposerImpl};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use crate::foundation::composer_impl::{ComposerImpl, ERROR_BOUNDARY_KEY, RecomposeStep};
+use crate::foundation::composition_local::{CompositionLocalId, CompositionLocalMap, ProvidedValue};
 use crate::foundation::constraint::Constraints;
-use crate::foundation::layout_node::LayoutNode;
+use crate::foundation::derived_state::DerivedStateObserver;
+use crate::foundation::error_boundary::{ErrorBoundaryHandle, ErrorPhase};
+use crate::foundation::key::call_site_key;
+use crate::foundation::compose_node::ComposedNode;
+use crate::foundation::movable_content::MovableContent;
 use crate::foundation::recompose_scope_impl::RecomposeScope;
 use crate::foundation::remember_manager::RememberManager;
+use crate::foundation::remember_observer::RememberObserver;
+use crate::foundation::snapshot::Snapshot;
+use crate::foundation::slot_table_type::ObjectKey;
 use crate::foundation::snapshot_value::SnapShotValue;
 use crate::foundation::composer_impl::ApplierInType;
 
@@ -25,63 +34,238 @@ impl<T> ScopeUpdateScopeHelper for T where T: DerefMut<Target=dyn ScopeUpdateSco
     }
 }
 
+/// wraps a param of a restartable composable, see `RestartParamClone`
+#[doc(hidden)]
+pub struct RestartParam<'a, T>(pub &'a T);
+
+/// picked by `(&RestartParam(&param)).restart_param()` when the param can be cloned into the restart block
+#[doc(hidden)]
+pub trait RestartParamClone<T> {
+    fn restart_param(&self) -> Option<Rc<dyn Fn() -> T>>;
+}
+
+impl<T: Clone + 'static> RestartParamClone<T> for RestartParam<'_, T> {
+    fn restart_param(&self) -> Option<Rc<dyn Fn() -> T>> {
+        let value = self.0.clone();
+        Some(Rc::new(move || value.clone()))
+    }
+}
+
+/// picked for params that can not be cloned, the composable is then recomposed by its parent scope
+#[doc(hidden)]
+pub trait RestartParamFallback<T> {
+    fn restart_param(&self) -> Option<Rc<dyn Fn() -> T>>;
+}
+
+impl<T> RestartParamFallback<T> for &RestartParam<'_, T> {
+    fn restart_param(&self) -> Option<Rc<dyn Fn() -> T>> {
+        None
+    }
+}
+
+/// wraps a param of a composable, see `ChangedParamCompare`
+#[doc(hidden)]
+pub struct ChangedParam<'a, T>(pub &'a T);
+
+/// picked by `(&ChangedParam(&param)).changed()` when the param can be stored in the slot table and compared.
+/// the selection does not see lifetimes, so the macro never wraps params which borrow non-static data
+#[doc(hidden)]
+pub trait ChangedParamCompare {
+    fn changed(&self) -> bool;
+}
+
+impl<T: PartialEq + Clone + 'static> ChangedParamCompare for ChangedParam<'_, T> {
+    fn changed(&self) -> bool {
+        Composer::changed(self.0)
+    }
+}
+
+/// picked for params that can not be compared, they are always considered changed
+#[doc(hidden)]
+pub trait ChangedParamFallback {
+    fn changed(&self) -> bool;
+}
+
+impl<T> ChangedParamFallback for &ChangedParam<'_, T> {
+    fn changed(&self) -> bool {
+        true
+    }
+}
+
 #[derive(Default)]
 pub struct Composer {
-    pub(crate) compose_impl: Option<RefCell<ComposerImpl>>,
+    /// the compositions composing or applying changes right now, the innermost one receives the static dispatch
+    active: Vec<Rc<RefCell<ComposerImpl>>>,
+    /// every composition created on this thread which is still alive
+    compositions: Vec<Weak<RefCell<ComposerImpl>>>,
 }
 
 thread_local! {
     pub static COMPOSER: RefCell<Composer> = RefCell::new(Composer::default())
 }
 
+/// pops the composition pushed by `Composer::activate`, even if the action panics
+struct ActiveGuard;
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        COMPOSER.with(|local_composer| {
+            local_composer.borrow_mut().active.pop();
+        });
+    }
+}
+
 impl Composer {
-    pub(crate) fn attach_root_layout_node(root: Rc<RefCell<LayoutNode>>) -> bool {
-        COMPOSER.with(move |local_composer| {
+    pub(crate) fn register(composer: &Rc<RefCell<ComposerImpl>>) {
+        COMPOSER.with(|local_composer| {
             let mut local_composer = local_composer.borrow_mut();
-            let mut compose_impl = RefCell::new(ComposerImpl::new(root.clone()));
-            let result = compose_impl.borrow_mut().attach_root_layout_node(root);
-            local_composer.compose_impl = Some(compose_impl);
+            local_composer.compositions.retain(|composition| composition.strong_count() > 0);
+            local_composer.compositions.push(Rc::downgrade(composer));
+        })
+    }
 
-            result
+    fn unregister(composer: &Rc<RefCell<ComposerImpl>>) {
+        COMPOSER.with(|local_composer| {
+            local_composer.borrow_mut().compositions
+                .retain(|composition| composition.strong_count() > 0 && composition.as_ptr() != Rc::as_ptr(composer));
         })
     }
 
+    fn compositions() -> Vec<Rc<RefCell<ComposerImpl>>> {
+        COMPOSER.with(|local_composer| {
+            local_composer.borrow().compositions.iter().filter_map(Weak::upgrade).collect()
+        })
+    }
+
+    /// run `action` with `composer` as the target of the static dispatch
+    pub(crate) fn activate<R>(composer: &Rc<RefCell<ComposerImpl>>, action: impl FnOnce() -> R) -> R {
+        COMPOSER.with(|local_composer| {
+            local_composer.borrow_mut().active.push(composer.clone());
+        });
+        let _guard = ActiveGuard;
+        action()
+    }
+
+    fn active_composer() -> Option<Rc<RefCell<ComposerImpl>>> {
+        COMPOSER.with(|local_composer| local_composer.try_borrow().ok()?.active.last().cloned())
+    }
+
     pub(crate) fn static_dispatch<R>(action: impl FnOnce(&ComposerImpl) -> R) -> R {
-        COMPOSER.with(|local_composer| action(local_composer.borrow().compose_impl.as_ref().unwrap().borrow().deref()))
+        let composer = Self::active_composer().expect("no composition is active on this thread");
+        let result = action(composer.borrow().deref());
+        result
     }
 
     pub(crate) fn static_dispatch_mut<R>(action: impl FnOnce(&mut ComposerImpl) -> R) -> R {
-        COMPOSER.with(|local_composer| action(local_composer.borrow().compose_impl.as_ref().unwrap().borrow_mut().deref_mut()))
+        let composer = Self::active_composer().expect("no composition is active on this thread");
+        let result = action(composer.borrow_mut().deref_mut());
+        result
     }
 
+    /// like `static_dispatch`, but returns None outside of a composition or while the composer is busy
+    pub(crate) fn try_static_dispatch<R>(action: impl FnOnce(&ComposerImpl) -> Option<R>) -> Option<R> {
+        let composer = Self::active_composer()?;
+        let composer = composer.try_borrow().ok()?;
+        action(composer.deref())
+    }
+
+    /// dispose every composition of this thread
     pub fn destroy() {
-        Self::static_dispatch_mut(|composer| composer.destroy());
-        COMPOSER.with(|local_composer| {
-            local_composer.borrow_mut().compose_impl = None;
-        })
+        Self::compositions().iter().for_each(|composer| Self::activate(composer, Self::dispose_active));
     }
 
-    pub(crate) fn detach_root_layout_node() {
-        Self::static_dispatch_mut(|composer| composer.detach_root_layout_node())
+    /// apply what is left of the last composition, then forget everything the active composition
+    /// remembered and release its nodes, the composition is no longer recomposed afterwards
+    pub(crate) fn dispose_active() {
+        let composer = Self::active_composer().expect("no composition is active on this thread");
+        if composer.borrow().is_disposed() {
+            return;
+        }
+
+        Self::apply_active_changes();
+        Self::apply_active_deferred_changes();
+        let mut remember_dispatcher = composer.borrow_mut().dispose();
+        remember_dispatcher.dispatch_remember_observers();
+        Self::unregister(&composer);
     }
 
     pub fn start_group(hash: u64) {
         Self::static_dispatch_mut(move |composer| composer.start_group(hash));
     }
 
+    /// start a group for a call of the composable whose key is `hash`, each call site gets a group of its own
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn start_call_site_group(hash: u64, name: &'static str) {
+        let hash = call_site_key(hash);
+        Self::static_dispatch_mut(move |composer| {
+            composer.start_group(hash);
+            composer.record_source_name(hash, name);
+        });
+    }
+
+    pub(crate) fn start_movable_group(hash: u64, object_key: Rc<dyn ObjectKey>) {
+        Self::static_dispatch_mut(move |composer| composer.start_movable_group(hash, Some(object_key)));
+    }
+
+    pub(crate) fn start_reusable_group(hash: u64, object_key: Rc<dyn ObjectKey>) {
+        Self::static_dispatch_mut(move |composer| composer.start_reusable_group(hash, object_key));
+    }
+
+    pub(crate) fn end_reusable_group() {
+        Self::static_dispatch_mut(move |composer| composer.end_reusable_group());
+    }
+
+    pub(crate) fn deactivate_to_end_group(changed: bool) {
+        Self::static_dispatch_mut(move |composer| composer.deactivate_to_end_group(changed));
+    }
+
+    pub(crate) fn end_movable_group() {
+        Self::static_dispatch_mut(move |composer| composer.end(false));
+    }
+
+    pub(crate) fn start_movable_content(content: MovableContent) {
+        Self::static_dispatch_mut(move |composer| composer.start_movable_content(content));
+    }
+
+    pub(crate) fn end_movable_content() {
+        Self::static_dispatch_mut(move |composer| composer.end_movable_content());
+    }
+
+    pub(crate) fn start_providers(values: Vec<ProvidedValue>) {
+        Self::static_dispatch_mut(move |composer| composer.start_providers(values));
+    }
+
+    pub(crate) fn end_providers() {
+        Self::static_dispatch_mut(move |composer| composer.end_providers());
+    }
+
+    pub(crate) fn composition_local(local: CompositionLocalId) -> Option<Rc<dyn Any>> {
+        // outside of a composition only the default value is visible
+        Self::try_static_dispatch(|composer| composer.composition_local(local))
+    }
+
+    pub(crate) fn current_composition_locals() -> Option<Rc<CompositionLocalMap>> {
+        Self::try_static_dispatch(|composer| composer.current_composition_locals())
+    }
+
     pub(crate) fn start_node() {
         Self::static_dispatch_mut(move |composer| composer.start_node());
     }
 
-    pub(crate) fn create_node() {
-        Self::static_dispatch_mut(move |composer| composer.create_node())
+    pub(crate) fn start_reusable_node() {
+        Self::static_dispatch_mut(move |composer| composer.start_reusable_node());
     }
 
-    pub(crate) fn use_node() -> Rc<RefCell<LayoutNode>> {
+    pub(crate) fn create_node(factory: impl FnOnce() -> ComposedNode + 'static) {
+        Self::static_dispatch_mut(move |composer| composer.create_node(factory))
+    }
+
+    pub(crate) fn use_node() -> ComposedNode {
         Self::static_dispatch_mut(move |composer| composer.use_node())
     }
 
-    fn record_fix_up(fix_up: impl FnOnce(&mut dyn Applier<Rc<RefCell<LayoutNode>>>, &mut dyn RememberManager) + 'static) {
+    fn record_fix_up(fix_up: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
         Self::static_dispatch_mut(move |composer| composer.record_fix_up(fix_up))
     }
 
@@ -94,16 +278,49 @@ impl Composer {
     }
 
     pub(crate) fn cache<R, T>(keys: &R, calculation: impl FnOnce() -> T) -> SnapShotValue<T>
-        where T: 'static, R: Sized + PartialEq<R> + 'static {
-        Self::static_dispatch_mut(move |composer| composer.cache(keys, calculation))
+        where T: 'static, R: Sized + PartialEq<R> + Clone + 'static {
+        if let Some(cached) = Self::static_dispatch_mut(move |composer| composer.cache(keys)) {
+            return cached;
+        }
+
+        // calculate outside of the composer borrow, the calculation may read composer state
+        let value = calculation();
+        Self::static_dispatch_mut(move |composer| composer.update_cached_value(value))
     }
 
+    /// apply the changes recorded by every composition of this thread
     pub fn apply_changes() {
-        Self::static_dispatch_mut(move |composer| composer.apply_changes())
+        Self::compositions().iter().for_each(|composer| Self::activate(composer, Self::apply_active_changes));
     }
 
     pub fn apply_deferred_changes() {
-        Self::static_dispatch_mut(move |composer| composer.apply_deferred_changes())
+        Self::compositions().iter().for_each(|composer| Self::activate(composer, Self::apply_active_deferred_changes));
+    }
+
+    pub(crate) fn apply_active_changes() {
+        // remember observers and side effects run user code, they are dispatched once the composer is released
+        let mut remember_dispatcher = Self::static_dispatch_mut(move |composer| composer.apply_changes());
+        remember_dispatcher.dispatch_remember_observers();
+        remember_dispatcher.dispatch_side_effects();
+    }
+
+    pub(crate) fn apply_active_deferred_changes() {
+        let mut remember_dispatcher = Self::static_dispatch_mut(move |composer| composer.apply_deferred_changes());
+        remember_dispatcher.dispatch_remember_observers();
+        remember_dispatcher.dispatch_side_effects();
+    }
+
+    pub(crate) fn remember_observer<T: RememberObserver>(changed: bool, factory: impl FnOnce() -> T) -> Rc<RefCell<T>> {
+        if let Some(remembered) = Self::static_dispatch_mut(move |composer| composer.remembered_observer(changed)) {
+            return remembered;
+        }
+
+        let observer = factory();
+        Self::static_dispatch_mut(move |composer| composer.update_remembered_observer(observer))
+    }
+
+    pub(crate) fn record_side_effect(effect: impl FnOnce() + 'static) {
+        Self::static_dispatch_mut(move |composer| composer.record_side_effect(effect))
     }
 
     pub(crate) fn end_node() {
@@ -114,40 +331,146 @@ impl Composer {
         Self::static_dispatch(move |composer| composer.inserting())
     }
 
+    /// the hash of the keys of the groups enclosing the current slot, it identifies the position in the composition
+    pub(crate) fn compound_hash() -> u64 {
+        Self::static_dispatch(move |composer| composer.hash)
+    }
+
     pub fn end_group(hash: u64) {
         Self::static_dispatch_mut(move |composer| composer.end_group(hash))
     }
 
     pub fn validate_group() {
-        Self::static_dispatch_mut(move |composer| composer.validate_group())
+        Self::compositions().iter().for_each(|composer| composer.borrow().validate_group())
     }
 
     pub fn debug_print() {
-        Self::static_dispatch(move |composer| composer.debug_print())
+        Self::compositions().iter().for_each(|composer| composer.borrow().debug_print())
     }
 
     pub fn recompose_scope() -> Option<Rc<RefCell<dyn RecomposeScope>>> {
         Self::static_dispatch_mut(move |composer| composer.recompose_scope())
     }
 
-    pub fn do_set_content(content: impl Fn()) {
-        COMPOSER.with(|local_composer| {
-            local_composer.borrow().compose_impl.as_ref().unwrap().borrow_mut().start_root();
-            content();
-            local_composer.borrow().compose_impl.as_ref().unwrap().borrow_mut().end_root();
+    /// run the calculation of a derived state, the reads it performs are not recorded in the current scope
+    pub(crate) fn observe_derived_state<R>(calculation: impl FnOnce() -> R) -> R {
+        let observe = |action: fn(&mut ComposerImpl)| {
+            let Some(composer) = Self::active_composer() else {
+                return false;
+            };
+            let Ok(mut composer) = composer.try_borrow_mut() else {
+                return false;
+            };
+            action(composer.deref_mut());
+            true
+        };
+
+        let started = observe(DerivedStateObserver::start);
+        let result = calculation();
+        if started {
+            observe(DerivedStateObserver::done);
+        }
+        result
+    }
+
+    pub(crate) fn set_active_content(content: Rc<dyn Fn()>) {
+        Self::static_dispatch_mut(|composer| {
+            composer.root_content = Some(content.clone());
+            composer.start_root();
         });
+        content();
+        Self::static_dispatch_mut(|composer| composer.end_root());
     }
 
-    pub fn do_compose_validate_structure(content: impl Fn()) {
-        COMPOSER.with(|local_composer| {
-            local_composer.borrow().compose_impl.as_ref().unwrap().borrow_mut().start_root();
+    /// recompose the scopes invalidated since the last composition of every composition of this thread,
+    /// returns false if there was nothing to recompose
+    pub fn recompose() -> bool {
+        Snapshot::send_apply_notifications();
+        Self::compositions().iter()
+            .fold(false, |recomposed, composer| Self::activate(composer, Self::recompose_active) || recomposed)
+    }
+
+    pub(crate) fn recompose_active() -> bool {
+        let Some(root_invalid) = Self::static_dispatch_mut(|composer| composer.start_recompose()) else {
+            return false;
+        };
+
+        if root_invalid {
+            if let Some(content) = Self::static_dispatch(|composer| composer.root_content.clone()) {
+                content();
+            }
+        } else {
+            Self::recompose_to_group_end();
+        }
+
+        Self::static_dispatch_mut(|composer| composer.end_recompose(root_invalid));
+        true
+    }
+
+    pub fn has_invalidations() -> bool {
+        Snapshot::send_apply_notifications();
+        Self::compositions().iter().any(|composer| composer.borrow().has_invalidations())
+    }
+
+    fn recompose_to_group_end() {
+        loop {
+            match Self::static_dispatch_mut(|composer| composer.next_recompose_step()) {
+                RecomposeStep::Restart(scope) => {
+                    let block = scope.borrow_mut().take_block();
+                    if let Some(mut block) = block {
+                        block();
+                        scope.borrow_mut().restore_block(block);
+                    }
+                }
+                RecomposeStep::Enter => {
+                    Self::recompose_to_group_end();
+                    Self::static_dispatch_mut(|composer| composer.exit_recompose_group());
+                }
+                RecomposeStep::EnterErrorBoundary(boundary, checkpoint) => {
+                    let recomposed = catch_unwind(AssertUnwindSafe(|| {
+                        Self::recompose_to_group_end();
+                        Self::static_dispatch_mut(|composer| composer.exit_recompose_group());
+                    }));
+
+                    if let Err(panic) = recomposed {
+                        Self::static_dispatch_mut(|composer| composer.rollback_error_boundary(checkpoint)).dispatch_abandoned();
+                        boundary.report(ErrorPhase::Composition, panic);
+                    }
+                }
+                RecomposeStep::End => return,
+            }
+        }
+    }
+
+    /// compose `content` in the content group of the error boundary `boundary`. when it panics the
+    /// slots and changes it produced are rolled back and the panic is returned
+    pub(crate) fn compose_error_boundary(boundary: ErrorBoundaryHandle, content: impl FnOnce()) -> Result<(), Box<dyn Any + Send>> {
+        let checkpoint = Self::static_dispatch_mut(|composer| composer.checkpoint());
+        let composed = catch_unwind(AssertUnwindSafe(|| {
+            Self::static_dispatch_mut(|composer| composer.start_movable_group(ERROR_BOUNDARY_KEY, Some(Rc::new(boundary))));
             content();
-            local_composer.borrow().compose_impl.as_ref().unwrap().borrow_mut().end_root();
-        });
+            Self::static_dispatch_mut(|composer| composer.end(false));
+        }));
+
+        if composed.is_err() {
+            Self::static_dispatch_mut(|composer| composer.rollback(checkpoint)).dispatch_abandoned();
+        }
+        composed
+    }
+
+    pub(crate) fn compose_active_validate_structure(content: impl Fn()) {
+        Self::static_dispatch_mut(|composer| composer.start_root());
+        content();
+        Self::static_dispatch_mut(|composer| composer.end_root());
     }
 
-    pub fn start_restart_group() {
-        Self::static_dispatch_mut(move |composer| composer.start_restart_group())
+    /// start the group of a call of the composable function `name` whose key is `hash`
+    #[doc(hidden)]
+    pub fn start_restart_group(hash: u64, name: &'static str) {
+        Self::static_dispatch_mut(move |composer| {
+            composer.start_restart_group(hash);
+            composer.record_source_name(hash, name);
+        })
     }
 
     pub fn end_restart_group() -> Option<Rc<RefCell<dyn ScopeUpdateScope>>> {
@@ -158,7 +481,14 @@ impl Composer {
         Self::static_dispatch(move |composer| composer.skipping())
     }
 
+    /// skip the rest of the current restart group, invalid scopes inside of it still recompose
     pub fn skip_to_end() {
-        Self::static_dispatch_mut(move |composer| composer.skip_to_end())
+        Self::static_dispatch_mut(move |composer| composer.skip_to_end());
+        Self::recompose_to_group_end();
+    }
+
+    /// store `value` in the next slot, returns whether it differs from the value stored by the last composition
+    pub fn changed<T: PartialEq + Clone + 'static>(value: &T) -> bool {
+        Self::static_dispatch_mut(move |composer| composer.changed(value))
     }
 }
\ No newline at end of file
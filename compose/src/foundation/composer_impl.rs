@@ -1,26 +1,34 @@
 use crate::foundation::composer::ScopeUpdateScope;
-use crate::foundation::recompose_scope_impl::RecomposeScopeImpl;
-use std::{cell::RefCell, rc::Rc};
+use crate::foundation::recompose_scope_impl::{Invalidations, RecomposeScopeImpl};
+use std::{cell::RefCell, rc::{Rc, Weak}};
 use std::alloc::Layout;
 use std::any::Any;
 use std::cell::{Ref, RefMut};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut};
 use crate::foundation::application_applier::ApplicationApplier;
 use crate::foundation::applier::Applier;
-use crate::foundation::compose_node_lifecycle_callback::ComposeNodeLifecycleCallback;
-use crate::foundation::composition::Composition;
+use crate::foundation::change_list::ChangeList;
+use crate::foundation::composition_local::{CompositionLocalId, CompositionLocalMap, ProvidedValue};
 use crate::foundation::derived_state::DerivedStateObserver;
+use crate::foundation::error_boundary::ErrorBoundaryHandle;
+use crate::foundation::movable_content::MovableContent;
 use crate::foundation::recompose_scope_impl::RecomposeScope;
 use crate::foundation::remember_manager::{RememberEventDispatcher, RememberManager};
+use crate::foundation::remember_observer::RememberObserver;
 
+use crate::foundation::composer::Composer;
 use crate::foundation::slot_table::{SlotTable, SlotReadWriter};
-use crate::foundation::slot_table_type::{GroupKindIndex, Slot};
+use crate::foundation::snapshot::{ObserverHandle, Snapshot, SnapshotId, StateObject, StateObjectId};
+use crate::foundation::slot_table_type::{GroupKindIndex, ObjectKey, Slot, SlotSnapshot};
 use crate::foundation::snapshot_value::SnapShotValue;
-use crate::foundation::ui_applier::UiApplier;
+use crate::foundation::compose_node::ComposedNode;
+use crate::foundation::inspector::{inspect_slots, InspectorGroup};
+use crate::foundation::ui::frame_stats;
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
 
-use super::{constraint::Constraints, layout_node::LayoutNode, slot_table_type::GroupKind};
+use super::{constraint::Constraints, slot_table_type::GroupKind};
 
 #[derive(Debug)]
 pub(crate) enum ChangeType {
@@ -30,7 +38,7 @@ pub(crate) enum ChangeType {
     DeferredChange,
 }
 
-pub(crate) type ApplierInType = Rc<RefCell<LayoutNode>>;
+pub(crate) type ApplierInType = ComposedNode;
 pub(crate) type ChangeFn = dyn FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager);
 
 pub(crate) struct Change {
@@ -60,6 +68,74 @@ impl Debug for Change {
 
 struct Pending {}
 
+/// a read of a state object by a scope, along with the scope epoch at the time of the read
+#[derive(Clone)]
+struct Observation {
+    scope: Weak<RefCell<RecomposeScopeImpl>>,
+    epoch: usize,
+    /// set when the state object is a dependency of a derived state read by the scope,
+    /// the scope is only invalidated once the derived value moves past the recorded record
+    derived: Option<(Rc<dyn StateObject>, SnapshotId)>,
+}
+
+impl Observation {
+    fn is_same(&self, other: &Observation) -> bool {
+        self.epoch == other.epoch && self.scope.ptr_eq(&other.scope) && match (&self.derived, &other.derived) {
+            (Some((derived, _)), Some((other_derived, _))) => derived.state_id() == other_derived.state_id(),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+type Observations = Rc<RefCell<HashMap<StateObjectId, Vec<Observation>>>>;
+
+/// stored in the first slot of a provider group
+struct ProviderState {
+    /// the holders of the values provided by this group
+    provided: CompositionLocalMap,
+    /// the holders of every local visible inside of this group
+    locals: Rc<CompositionLocalMap>,
+}
+
+pub(crate) enum RecomposeStep {
+    /// the slot at the cursor is the restart group of an invalid scope, run its block
+    Restart(Rc<RefCell<RecomposeScopeImpl>>),
+    /// entered a group holding invalid scopes, recompose it and call `exit_recompose_group`
+    Enter,
+    /// entered the content group of an error boundary, recompose it like `Enter`. if it panics the
+    /// composer is rolled back to `CompositionCheckpoint` by `rollback_error_boundary`
+    EnterErrorBoundary(ErrorBoundaryHandle, CompositionCheckpoint),
+    /// no invalid scope left in the current group
+    End,
+}
+
+/// the state of the composer before the content of an error boundary, restored when the content panics
+pub(crate) struct CompositionCheckpoint {
+    hash: u64,
+    depth: usize,
+    node_expected: bool,
+    inserting: bool,
+    reusing: bool,
+    reusing_group: Option<usize>,
+    read_writer: SlotReadWriter,
+    slots: SlotSnapshot,
+    changes: usize,
+    remembered_observers: usize,
+    insert_up_fix_up: usize,
+    invalidate_stack: usize,
+    providers_invalid: bool,
+    providers_invalid_stack: Vec<bool>,
+    movable_contents: HashMap<*const (), Weak<RefCell<GroupKind>>>,
+    removed_movable_contents: HashMap<*const (), Slot>,
+    composed_movable_contents: HashSet<*const ()>,
+    movable_content_inserting_stack: Vec<bool>,
+    derived_state_depth: usize,
+    node_index: usize,
+    node_index_stack: Vec<usize>,
+    group_node_index_stack: Vec<usize>,
+}
+
 pub(crate) struct ComposerImpl {
     pub(crate) hash: u64,
     pub(crate) depth: usize,
@@ -67,9 +143,13 @@ pub(crate) struct ComposerImpl {
     pub(crate) sequence: usize,
 
     pub(crate) inserting: bool,
-    pub(crate) root: Option<Rc<RefCell<LayoutNode>>>,
-    pub(crate) layout_node_stack: Vec<Rc<RefCell<LayoutNode>>>,
-    pub(crate) composition: Composition,
+    /// set while the content of a reusable group is composed for a new key over the slots of the previous one
+    reusing: bool,
+    /// the depth of the reusable group which started reusing
+    reusing_group: Option<usize>,
+    pub(crate) root: Option<ComposedNode>,
+    pub(crate) layout_node_stack: Vec<ComposedNode>,
+    pub(crate) change_list: ChangeList,
 
     pub(crate) slot_table: SlotTable,
 
@@ -81,12 +161,41 @@ pub(crate) struct ComposerImpl {
     pending_stack: Vec<Option<Pending>>,
 
     invalidate_stack: Vec<Rc<RefCell<RecomposeScopeImpl>>>,
+    invalidations: Invalidations,
+    invalid_scopes: HashMap<*const RefCell<GroupKind>, Rc<RefCell<RecomposeScopeImpl>>>,
+    invalid_ancestors: HashMap<*const RefCell<GroupKind>, Weak<RefCell<GroupKind>>>,
+    pub(crate) root_content: Option<Rc<dyn Fn()>>,
+    observations: Observations,
+    snapshot_observers: Vec<ObserverHandle>,
+    derived_state_depth: usize,
+    /// set while composing the content of a provider whose static values changed, nothing skips in there
+    providers_invalid: bool,
+    providers_invalid_stack: Vec<bool>,
+    /// the locals visible where a subcomposition was created, they are visible to all of its content
+    parent_locals: Option<Rc<CompositionLocalMap>>,
+    /// the group of every movable content by the identity of the content
+    movable_contents: HashMap<*const (), Weak<RefCell<GroupKind>>>,
+    /// the groups of movable contents removed by this composition, until they are invoked at another place
+    removed_movable_contents: HashMap<*const (), Slot>,
+    /// the movable contents composed by this composition already
+    composed_movable_contents: HashSet<*const ()>,
+    movable_content_inserting_stack: Vec<bool>,
+    /// the name of the composable function of each group key, for the inspector
+    source_names: HashMap<u64, &'static str>,
+    /// set by `dispose`, the composition can not compose again afterwards
+    disposed: bool,
+    /// the observers created since the changes were applied last, they are abandoned when the
+    /// composition which created them is rolled back
+    remembered_observers: Vec<Rc<RefCell<dyn RememberObserver>>>,
 
     previous_remove: i32,
+    previous_move_from: i32,
+    previous_move_to: i32,
     previous_count: usize,
 
     node_index: usize,
     node_index_stack: Vec<usize>,
+    group_node_index_stack: Vec<usize>,
 
     pending: Option<Pending>,
 }
@@ -105,7 +214,13 @@ impl Debug for ComposerImpl {
 }
 
 pub(crate) const ROOT_KEY: u64 = 100;
+pub(crate) const PROVIDER_KEY: u64 = 201;
+pub(crate) const MOVABLE_CONTENT_KEY: u64 = 206;
+pub(crate) const REUSE_KEY: u64 = 207;
+pub(crate) const ERROR_BOUNDARY_KEY: u64 = 208;
+pub(crate) const ERROR_BOUNDARY_FALLBACK_KEY: u64 = 209;
 pub(crate) const NODE_KEY: u64 = 125;
+pub(crate) const REUSABLE_NODE_KEY: u64 = 126;
 
 impl ComposerImpl {
     pub(crate) fn destroy(&mut self) {
@@ -114,33 +229,62 @@ impl ComposerImpl {
         self.fix_up.clear();
         self.insert_up_fix_up.clear();
         self.invalidate_stack.clear();
+        self.invalidations.borrow_mut().clear();
+        self.root_content = None;
+        self.movable_contents.clear();
+        self.removed_movable_contents.clear();
+        self.observations.borrow_mut().clear();
+        self.snapshot_observers.drain(..).for_each(ObserverHandle::dispose);
+        self.source_names.clear();
+        self.remembered_observers.clear();
     }
 
-    pub(crate) fn attach_root_layout_node(&mut self, root: Rc<RefCell<LayoutNode>>) -> bool {
-        if self.root.is_some() {
-            return false;
-        }
+    /// forget every observer remembered by the composition, last remembered first, release its nodes and
+    /// remove them from the root, the returned dispatcher runs the observers once the composer is released
+    pub(crate) fn dispose(&mut self) -> RememberEventDispatcher {
+        let slots = std::mem::take(&mut *self.slot_table.slots.borrow_mut());
+        let removed_movable_contents = std::mem::take(&mut self.removed_movable_contents);
+        slots.iter().chain(removed_movable_contents.values()).for_each(|slot| self.record_forgetting(slot));
+
+        let mut root_node_count = 0;
+        slots.iter().for_each(|slot| slot.borrow().visit_top_level_node(&mut |_| root_node_count += 1));
+        self.change_list.record(move |applier, _| {
+            if root_node_count > 0 {
+                applier.remove(0, root_node_count);
+            }
+            applier.clear();
+        });
 
-        self.root = Some(root);
-        true
+        let remember_dispatcher = self.change_list.apply_changes();
+        self.destroy();
+        self.disposed = true;
+        remember_dispatcher
     }
 
-    pub(crate) fn detach_root_layout_node(&mut self) {
-        self.root = None;
+    pub(crate) fn is_disposed(&self) -> bool {
+        self.disposed
     }
 
     pub(crate) fn inserting(&self) -> bool {
         self.inserting
     }
 
+    pub(crate) fn reusing(&self) -> bool {
+        self.reusing
+    }
+
+    /// whether the current restart group may skip its body, which is the case once its params
+    /// compared equal unless it is new or its scope was invalidated
     pub(crate) fn skipping(&self) -> bool {
-        self.read_writer.skipping()
+        !self.inserting && !self.reusing && !self.providers_invalid && self.invalidate_stack.last()
+            .map(|scope| !scope.borrow().requires_recompose())
+            .unwrap_or(false)
     }
 
-    pub(crate) fn apply<V: 'static>(&mut self, value: V, block: impl FnOnce(&mut LayoutNode, V) + 'static) {
-        let operation = move |applier: &mut dyn Applier<Rc<RefCell<LayoutNode>>>, _: &mut dyn RememberManager| {
-            let mut layout_node = applier.get_current().borrow_mut();
-            block(layout_node.deref_mut(), value);
+    pub(crate) fn apply<T: 'static, V: 'static>(&mut self, value: V, block: impl FnOnce(&mut T, V) + 'static) {
+        let operation = move |applier: &mut dyn Applier<ApplierInType>, _: &mut dyn RememberManager| {
+            let node = applier.get_current().downcast::<T>().expect("node updated as a node of another tree");
+            block(node.borrow_mut().deref_mut(), value);
         };
 
         if self.inserting {
@@ -150,24 +294,253 @@ impl ComposerImpl {
         }
     }
 
-    pub(crate) fn skip_to_end(&self) {}
+    /// keep the current restart group as is, the rest of it is walked by `recompose_to_group_end`
+    pub(crate) fn skip_to_end(&mut self) {
+        if let Some(scope) = self.invalidate_stack.last() {
+            scope.borrow_mut().skip();
+        }
+    }
 
-    pub(crate) fn start_root(&mut self) {
+    fn reset_root(&mut self) {
         {
             let writer = &mut self.read_writer;
             writer.slot_index_stack.clear();
             writer.slot_visit_index = 0;
         }
-        self.start_group(ROOT_KEY);
+        self.node_index = 0;
+        self.node_index_stack.clear();
+        self.group_node_index_stack.clear();
+        self.providers_invalid = false;
+        self.providers_invalid_stack.clear();
+        self.reusing = false;
+        self.reusing_group = None;
+        self.movable_content_inserting_stack.clear();
     }
 
-    pub(crate) fn end_root(&mut self) {
-        self.end_group(ROOT_KEY);
+    fn finish_root(&mut self) {
+        self.realize_movement();
+
+        // the movable contents which were not invoked at another place leave the composition
+        for slot in std::mem::take(&mut self.removed_movable_contents).into_values() {
+            self.record_forgetting(&slot);
+        }
+        self.composed_movable_contents.clear();
+        self.movable_contents.retain(|_, slot| slot.strong_count() > 0);
         {
             let writer = &mut self.read_writer;
             writer.slot_index_stack.clear();
             writer.slot_visit_index = 0;
         }
+
+        self.invalid_scopes.clear();
+        self.invalid_ancestors.clear();
+    }
+
+    pub(crate) fn start_root(&mut self) {
+        // skipped groups still recompose the invalid scopes they contain
+        self.collect_invalid_scopes();
+        self.reset_root();
+        self.start_restart_group(ROOT_KEY);
+    }
+
+    pub(crate) fn end_root(&mut self) {
+        self.end_restart_group();
+        self.finish_root();
+    }
+
+    pub(crate) fn has_invalidations(&self) -> bool {
+        !self.invalidations.borrow().is_empty()
+    }
+
+    /// move the scopes invalidated since the last composition into `invalid_scopes`, and their
+    /// enclosing groups into `invalid_ancestors`
+    fn collect_invalid_scopes(&mut self) {
+        let invalidations = std::mem::take(self.invalidations.borrow_mut().deref_mut());
+        for scope in invalidations {
+            let anchor = {
+                let scope = scope.borrow();
+                match scope.anchor() {
+                    Some(anchor) if scope.is_invalid() => anchor,
+                    _ => continue,
+                }
+            };
+
+            let mut parent = anchor.borrow().parent();
+            while let Some(slot) = parent {
+                self.invalid_ancestors.insert(Rc::as_ptr(&slot), Rc::downgrade(&slot));
+                parent = slot.borrow().parent();
+            }
+            self.invalid_scopes.insert(Rc::as_ptr(&anchor), scope);
+        }
+    }
+
+    /// collect the scopes invalidated since the last composition and enter the root group,
+    /// returns `None` if nothing needs to recompose, otherwise whether the root scope itself is invalid
+    pub(crate) fn start_recompose(&mut self) -> Option<bool> {
+        self.collect_invalid_scopes();
+        if self.invalid_scopes.is_empty() {
+            return None;
+        }
+
+        let root_invalid = self.slot_table.slots.borrow().first()
+            .map(|root| self.invalid_scopes.contains_key(&Rc::as_ptr(root)))
+            .unwrap_or(false);
+
+        self.reset_root();
+        self.start_group(ROOT_KEY);
+        if root_invalid {
+            self.add_recompose_scope();
+        }
+
+        Some(root_invalid)
+    }
+
+    pub(crate) fn end_recompose(&mut self, root_invalid: bool) {
+        if root_invalid {
+            self.invalidate_stack.pop();
+        }
+        self.end_group(ROOT_KEY);
+        self.finish_root();
+    }
+
+    /// advance the cursor to the next slot that needs recomposition within the current group
+    pub(crate) fn next_recompose_step(&mut self) -> RecomposeStep {
+        while let Some(slot) = self.read_writer.current_slot() {
+            let slot_ptr = Rc::as_ptr(&slot);
+
+            let mut enter = self.invalid_ancestors.contains_key(&slot_ptr);
+            if let Some(scope) = self.invalid_scopes.get(&slot_ptr) {
+                let scope_ref = scope.borrow();
+                if scope_ref.is_invalid() {
+                    if scope_ref.has_block() {
+                        return RecomposeStep::Restart(scope.clone());
+                    }
+                    enter = true;
+                }
+            }
+
+            if enter {
+                let (is_node, key, object_key) = {
+                    let slot = slot.borrow();
+                    (slot.is_node(), slot.key(), slot.object_key())
+                };
+
+                let boundary = object_key.as_ref()
+                    .filter(|_| key == Some(ERROR_BOUNDARY_KEY))
+                    .and_then(|object_key| object_key.as_any().downcast_ref::<ErrorBoundaryHandle>().cloned());
+                if let Some(boundary) = boundary {
+                    let checkpoint = self.checkpoint();
+                    self.start_movable_group(ERROR_BOUNDARY_KEY, object_key);
+                    return RecomposeStep::EnterErrorBoundary(boundary, checkpoint);
+                }
+
+                if is_node {
+                    if key == Some(REUSABLE_NODE_KEY) {
+                        self.start_reusable_node();
+                    } else {
+                        self.start_node();
+                    }
+                    self.use_node();
+                } else if let Some(key) = key {
+                    self.start_movable_group(key, object_key);
+                }
+                return RecomposeStep::Enter;
+            }
+
+            self.node_index += self.read_writer.skip_slot();
+        }
+
+        RecomposeStep::End
+    }
+
+    pub(crate) fn exit_recompose_group(&mut self) {
+        self.skip_to_group_end();
+        let is_node = self.read_writer.parent().borrow().is_node();
+        self.end(is_node);
+    }
+
+    /// take a checkpoint to roll the composition of the following slots back to
+    pub(crate) fn checkpoint(&mut self) -> CompositionCheckpoint {
+        // the pending node operations belong to the slots before the checkpoint
+        self.realize_movement();
+        self.record_insert();
+
+        CompositionCheckpoint {
+            hash: self.hash,
+            depth: self.depth,
+            node_expected: self.node_expected,
+            inserting: self.inserting,
+            reusing: self.reusing,
+            reusing_group: self.reusing_group,
+            read_writer: self.read_writer.clone(),
+            slots: SlotSnapshot::take(&self.read_writer.current_slots()),
+            changes: self.change_list.len(),
+            remembered_observers: self.remembered_observers.len(),
+            insert_up_fix_up: self.insert_up_fix_up.len(),
+            invalidate_stack: self.invalidate_stack.len(),
+            providers_invalid: self.providers_invalid,
+            providers_invalid_stack: self.providers_invalid_stack.clone(),
+            movable_contents: self.movable_contents.clone(),
+            removed_movable_contents: self.removed_movable_contents.clone(),
+            composed_movable_contents: self.composed_movable_contents.clone(),
+            movable_content_inserting_stack: self.movable_content_inserting_stack.clone(),
+            derived_state_depth: self.derived_state_depth,
+            node_index: self.node_index,
+            node_index_stack: self.node_index_stack.clone(),
+            group_node_index_stack: self.group_node_index_stack.clone(),
+        }
+    }
+
+    /// undo the slot edits and drop the changes recorded since `checkpoint`, the cursor is put back
+    /// to where it was. the observers created since are never remembered, the returned dispatcher
+    /// abandons them once the composer is released
+    pub(crate) fn rollback(&mut self, checkpoint: CompositionCheckpoint) -> RememberEventDispatcher {
+        self.hash = checkpoint.hash;
+        self.depth = checkpoint.depth;
+        self.node_expected = checkpoint.node_expected;
+        self.inserting = checkpoint.inserting;
+        self.reusing = checkpoint.reusing;
+        self.reusing_group = checkpoint.reusing_group;
+        self.read_writer = checkpoint.read_writer;
+        checkpoint.slots.restore();
+
+        self.previous_remove = -1;
+        self.previous_move_from = -1;
+        self.previous_move_to = -1;
+        self.previous_count = 0;
+        self.change_list.truncate(checkpoint.changes);
+        let mut remember_dispatcher = RememberEventDispatcher::new();
+        self.remembered_observers.drain(checkpoint.remembered_observers..)
+            .for_each(|observer| remember_dispatcher.abandoning(observer));
+        self.fix_up.clear();
+        self.insert_up_fix_up.truncate(checkpoint.insert_up_fix_up);
+
+        self.invalidate_stack.truncate(checkpoint.invalidate_stack);
+        self.providers_invalid = checkpoint.providers_invalid;
+        self.providers_invalid_stack = checkpoint.providers_invalid_stack;
+        self.movable_contents = checkpoint.movable_contents;
+        self.removed_movable_contents = checkpoint.removed_movable_contents;
+        self.composed_movable_contents = checkpoint.composed_movable_contents;
+        self.movable_content_inserting_stack = checkpoint.movable_content_inserting_stack;
+        self.derived_state_depth = checkpoint.derived_state_depth;
+        self.node_index = checkpoint.node_index;
+        self.node_index_stack = checkpoint.node_index_stack;
+        self.group_node_index_stack = checkpoint.group_node_index_stack;
+        remember_dispatcher
+    }
+
+    /// roll back the content group entered by `RecomposeStep::EnterErrorBoundary` and step over it,
+    /// the group keeps the content of the last composition
+    pub(crate) fn rollback_error_boundary(&mut self, checkpoint: CompositionCheckpoint) -> RememberEventDispatcher {
+        let remember_dispatcher = self.rollback(checkpoint);
+        self.node_index += self.read_writer.skip_slot();
+        remember_dispatcher
+    }
+
+    fn skip_to_group_end(&mut self) {
+        while !self.read_writer.is_group_end() {
+            self.node_index += self.read_writer.skip_slot();
+        }
     }
 
     pub(crate) fn start_node(&mut self) {
@@ -179,6 +552,15 @@ impl ComposerImpl {
         self.node_expected = true
     }
 
+    pub(crate) fn start_reusable_node(&mut self) {
+        self.start(
+            REUSABLE_NODE_KEY,
+            None,
+            GroupKind::ReusableNode(),
+            None);
+        self.node_expected = true
+    }
+
     pub(crate) fn end_group(&mut self, hash: u64) {
         self.end(false);
     }
@@ -186,25 +568,205 @@ impl ComposerImpl {
     fn exit_group(&mut self, is_node: bool) {
         if is_node {
             self.node_index = self.node_index_stack.pop().unwrap();
+        } else {
+            let node_count = self.node_index - self.group_node_index_stack.pop().unwrap();
+            self.read_writer.parent().borrow_mut().set_node_count(node_count);
         }
         self.read_writer.exit_group();
     }
 
     pub(crate) fn start_group(&mut self, hash: u64) {
+        self.start_movable_group(hash, None);
+    }
+
+    /// start a group which is identified by `object_key` as well, it is moved along with its nodes
+    /// when its siblings are reordered
+    pub(crate) fn start_movable_group(&mut self, hash: u64, object_key: Option<Rc<dyn ObjectKey>>) {
         self.start(
             hash,
-            None,
+            object_key,
             GroupKind::Group {
                 key: hash,
+                object_key: None,
                 depth: self.depth,
-                skipping: false,
+                node_count: 0,
                 slot_data: vec![].wrap_with_rc_refcell(),
+                parent: Weak::new(),
             },
             None,
         );
     }
 
-    pub(crate) fn create_node(&mut self) {
+    /// start a group whose content is composed over the slots and reusable nodes of the previous
+    /// `object_key` when it changed, instead of being removed and inserted again
+    pub(crate) fn start_reusable_group(&mut self, hash: u64, object_key: Rc<dyn ObjectKey>) {
+        if !self.inserting && self.read_writer.find_group(hash, Some(&object_key)).is_none() {
+            if let Some(slot) = self.read_writer.current_slot() {
+                if slot.borrow().key() == Some(hash) {
+                    slot.borrow_mut().set_object_key(object_key.clone());
+                    if !self.reusing {
+                        self.reusing = true;
+                        self.reusing_group = Some(self.depth);
+                    }
+                }
+            }
+        }
+
+        self.start_movable_group(hash, Some(object_key));
+    }
+
+    pub(crate) fn end_reusable_group(&mut self) {
+        self.end(false);
+        if self.reusing_group == Some(self.depth) {
+            self.reusing = false;
+            self.reusing_group = None;
+        }
+    }
+
+    /// take the rest of the current group out of the composition without removing its slots and
+    /// nodes, so that it can be reused once it is composed again. `changed` is false if it was
+    /// deactivated by the last composition already
+    pub(crate) fn deactivate_to_end_group(&mut self, changed: bool) {
+        if self.inserting {
+            return;
+        }
+
+        while let Some(slot) = self.read_writer.current_slot() {
+            if changed {
+                self.record_deactivating(&slot);
+            }
+            self.node_index += self.read_writer.skip_slot();
+        }
+    }
+
+    fn record_deactivating(&mut self, slot: &Slot) {
+        let mut forgotten = vec![];
+        slot.borrow_mut().deactivate(&mut forgotten);
+
+        let mut nodes = vec![];
+        slot.borrow().visit_node(&mut |node| nodes.push(node.clone()));
+        if forgotten.is_empty() && nodes.is_empty() {
+            return;
+        }
+
+        self.change_list.record(move |_, remember_manager| {
+            nodes.into_iter().for_each(|node| remember_manager.deactivate(node.lifecycle()));
+            forgotten.into_iter().for_each(|observer| remember_manager.forgetting(observer));
+        });
+    }
+
+    /// start the group of `content`, when the last composition composed it at another place its
+    /// slots and layout nodes are moved here
+    pub(crate) fn start_movable_content(&mut self, content: MovableContent) {
+        let id = content.id();
+        let object_key: Rc<dyn ObjectKey> = Rc::new(content);
+
+        // only the first invocation takes the previous content, another one composes a new copy
+        let first_invocation = self.composed_movable_contents.insert(id);
+        let moved = if first_invocation
+            && (self.inserting || self.read_writer.find_group(MOVABLE_CONTENT_KEY, Some(&object_key)).is_none()) {
+            self.take_movable_content(id)
+        } else {
+            None
+        };
+
+        self.movable_content_inserting_stack.push(self.inserting);
+        if let Some(slot) = moved {
+            self.insert_movable_content(slot);
+            // the moved slots are read like the slots of any existing group
+            self.inserting = false;
+        }
+
+        self.start_movable_group(MOVABLE_CONTENT_KEY, Some(object_key));
+        self.movable_contents.insert(id, Rc::downgrade(&self.read_writer.parent()));
+    }
+
+    pub(crate) fn end_movable_content(&mut self) {
+        self.end(false);
+        self.inserting = self.movable_content_inserting_stack.pop().unwrap();
+    }
+
+    /// take the group of the movable content `id` out of the place the last composition composed it at
+    fn take_movable_content(&mut self, id: *const ()) -> Option<Slot> {
+        if let Some(slot) = self.removed_movable_contents.remove(&id) {
+            return Some(slot);
+        }
+
+        // the place has not been visited by this composition yet
+        let slot = self.movable_contents.get(&id)?.upgrade()?;
+        let parent = slot.borrow().parent()?;
+        let slot_data = parent.borrow().slot_data()?;
+        let index = slot_data.borrow().iter().position(|child| Rc::ptr_eq(child, &slot))?;
+        slot_data.borrow_mut().remove(index);
+
+        // the groups up to the nearest node do not hold the moved nodes anymore
+        let node_count = slot.borrow().node_count();
+        let mut group = Some(parent);
+        while let Some(current) = group {
+            if current.borrow().is_node() {
+                break;
+            }
+            let count = current.borrow().node_count();
+            current.borrow_mut().set_node_count(count.saturating_sub(node_count));
+            group = current.borrow().parent();
+        }
+
+        Some(slot)
+    }
+
+    /// put the group of a moved content at the current position, and its nodes into the current node
+    fn insert_movable_content(&mut self, slot: Slot) {
+        self.read_writer.insert_moved_slot(slot.clone());
+
+        let mut nodes = vec![];
+        slot.borrow().visit_top_level_node(&mut |node| nodes.push(node.clone()));
+        if nodes.is_empty() {
+            return;
+        }
+
+        let index = self.node_index;
+        let insert = move |applier: &mut dyn Applier<ApplierInType>, _: &mut dyn RememberManager| {
+            nodes.into_iter().enumerate().for_each(|(offset, node)| {
+                applier.insert_bottom_up(index + offset, node);
+            });
+        };
+
+        // inside of a new node the nodes are inserted once it is created
+        if self.fix_up.is_empty() {
+            self.record_applier_operation(insert);
+        } else {
+            self.record_fix_up(insert);
+        }
+    }
+
+    /// keep the groups of the movable contents in `slot`, which is removed, for the place they are
+    /// invoked at later by this composition. returns whether `slot` is such a group itself
+    fn keep_movable_contents(&mut self, slot: &Slot) -> bool {
+        let id = {
+            let slot = slot.borrow();
+            slot.object_key()
+                .filter(|_| slot.key() == Some(MOVABLE_CONTENT_KEY))
+                .and_then(|object_key| object_key.as_any().downcast_ref::<MovableContent>().map(MovableContent::id))
+        };
+
+        if let Some(id) = id {
+            if self.composed_movable_contents.contains(&id) {
+                return false;
+            }
+            if let Some(previous) = self.removed_movable_contents.insert(id, slot.clone()) {
+                self.record_forgetting(&previous);
+            }
+            return true;
+        }
+
+        let Some(slot_data) = slot.borrow().slot_data() else {
+            return false;
+        };
+        slot_data.borrow_mut().retain(|child| !self.keep_movable_contents(child));
+        false
+    }
+
+    pub(crate) fn create_node(&mut self, factory: impl FnOnce() -> ComposedNode + 'static) {
         self.validate_node_expected();
 
         {
@@ -216,7 +778,7 @@ impl ComposerImpl {
             let slot = self.read_writer.parent();
             let slot_bottom_up = slot.clone();
             self.record_fix_up(move |applier, _| {
-                let node = LayoutNode::new();
+                let node = factory();
                 slot.borrow_mut().update_node(node.clone());
                 applier.insert_top_down(insert_index, node.clone());
                 applier.down(node);
@@ -231,28 +793,34 @@ impl ComposerImpl {
     }
 
     pub(crate) fn record_deferred_change(&mut self, deferred_change: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
-        self.composition.record_deferred_change(deferred_change);
+        self.change_list.record_deferred_change(deferred_change);
     }
 
-    pub(crate) fn apply_deferred_changes(&mut self) {
-        self.composition.apply_deferred_changes();
+    pub(crate) fn apply_deferred_changes(&mut self) -> RememberEventDispatcher {
+        self.change_list.apply_deferred_changes()
     }
 
-    pub(crate) fn use_node(&mut self) -> Rc<RefCell<LayoutNode>> {
+    pub(crate) fn use_node(&mut self) -> ComposedNode {
         self.validate_node_expected();
 
+        *self.node_index_stack.last_mut().unwrap() += 1;
+
         let node = self.read_writer.use_layout_node();
         self.read_writer.begin_use_layout_node(node.clone());
 
         let node_ref = node.clone();
-        self.record_applier_operation(move |_, _| {
-            node_ref.borrow_mut().on_reuse();
+        let reused = self.reusing.then(|| node.clone());
+        self.record_applier_operation(move |applier, remember_manager| {
+            if let Some(reused) = reused {
+                remember_manager.reuse(reused.lifecycle());
+            }
+            applier.down(node_ref);
         });
 
         node
     }
 
-    pub(crate) fn record_fix_up(&mut self, fix_up: impl FnOnce(&mut dyn Applier<Rc<RefCell<LayoutNode>>>, &mut dyn RememberManager) + 'static) {
+    pub(crate) fn record_fix_up(&mut self, fix_up: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
         self.fix_up.push(Change {
             change: Box::new(fix_up),
             change_type: ChangeType::FixUp,
@@ -260,7 +828,7 @@ impl ComposerImpl {
         self.sequence += 1;
     }
 
-    pub(crate) fn record_insert_up_fix_up(&mut self, insert_up_fix_up: impl FnOnce(&mut dyn Applier<Rc<RefCell<LayoutNode>>>, &mut dyn RememberManager) + 'static) {
+    pub(crate) fn record_insert_up_fix_up(&mut self, insert_up_fix_up: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
         self.insert_up_fix_up.push(Change {
             change: Box::new(insert_up_fix_up),
             change_type: ChangeType::InsertUpFixUp,
@@ -275,11 +843,13 @@ impl ComposerImpl {
     }
 
     fn record_applier_operation(&mut self, action: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
-        self.composition.record(action)
+        self.realize_movement();
+        self.change_list.record(action)
     }
 
     fn record_slot_editing_operation(&mut self, action: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
-        self.composition.record(action)
+        self.realize_movement();
+        self.change_list.record(action)
     }
 
     fn record_insert(&mut self) {
@@ -323,95 +893,295 @@ impl ComposerImpl {
         self.read_writer.validate();
     }
 
+    /// log the groups of this composition at debug level, they are not inspected unless it is enabled
     pub(crate) fn debug_print(&self) {
-        dbg!(self);
+        if log::log_enabled!(log::Level::Debug) {
+            self.inspect().iter().for_each(|group| log::debug!("composition tree\n{group}"));
+        }
     }
 
-    fn add_recompose_scope(&mut self) {
-        if self.inserting() {
-            let recompose_scope_impl = RecomposeScopeImpl::new().wrap_with_rc_refcell();
-            self.invalidate_stack.push(recompose_scope_impl.clone());
-            self.update_value(recompose_scope_impl.clone());
-            recompose_scope_impl.borrow_mut().start(0);
-        } else {
-            self.read_writer.skip_slot();
-            // perform recompose scope update
+    /// remember that the group just started with `key` is a call of the composable function `name`,
+    /// the groups which are not inserted had their name recorded when they were
+    pub(crate) fn record_source_name(&mut self, key: u64, name: &'static str) {
+        if self.inserting {
+            self.source_names.entry(key).or_insert(name);
         }
     }
 
-    pub(crate) fn start_restart_group(&mut self) {
+    pub(crate) fn inspect(&self) -> Vec<InspectorGroup> {
+        inspect_slots(&self.slot_table.slots.borrow(), &self.source_names)
+    }
+
+    fn add_recompose_scope(&mut self) {
+        let previous = self.next_slot();
+        let has_previous = previous.is_some();
+
+        let scope = match previous.and_then(|value| value.downcast::<RefCell<RecomposeScopeImpl>>().ok()) {
+            Some(scope) => scope,
+            None => {
+                let scope = RecomposeScopeImpl::new(self.invalidations.clone(), self.invalidate_stack.last());
+                self.update_value(scope.clone(), has_previous);
+                scope
+            }
+        };
+
+        scope.borrow_mut().start(Rc::downgrade(&self.read_writer.parent()));
+        self.invalidate_stack.push(scope);
+    }
+
+    pub(crate) fn start_restart_group(&mut self, hash: u64) {
+        self.start_group(hash);
         self.add_recompose_scope()
     }
 
     pub(crate) fn end_restart_group(&mut self) -> Option<Rc<RefCell<dyn ScopeUpdateScope>>> {
-        self.invalidate_stack.pop().map(|scope| scope as Rc<RefCell<dyn ScopeUpdateScope>>)
+        let scope = self.invalidate_stack.pop();
+        self.end(false);
+        scope.map(|scope| scope as Rc<RefCell<dyn ScopeUpdateScope>>)
+    }
+
+    /// the locals provided by the nearest provider group enclosing the current slot
+    pub(crate) fn current_composition_locals(&self) -> Option<Rc<CompositionLocalMap>> {
+        if self.read_writer.slot_stack.is_empty() {
+            return self.parent_locals.clone();
+        }
+
+        let mut group = Some(self.read_writer.parent());
+        while let Some(slot) = group {
+            if slot.borrow().key() == Some(PROVIDER_KEY) {
+                let state = slot.borrow().slot_data()
+                    .and_then(|slot_data| slot_data.borrow().first().cloned())
+                    .and_then(|first| match first.borrow().deref() {
+                        GroupKind::CustomType(value) => value.clone().downcast::<ProviderState>().ok(),
+                        _ => None,
+                    });
+                if let Some(state) = state {
+                    return Some(state.locals.clone());
+                }
+            }
+            group = slot.borrow().parent();
+        }
+
+        self.parent_locals.clone()
+    }
+
+    pub(crate) fn set_parent_locals(&mut self, parent_locals: Option<Rc<CompositionLocalMap>>) {
+        self.parent_locals = parent_locals;
+    }
+
+    pub(crate) fn composition_local(&self, local: CompositionLocalId) -> Option<Rc<dyn Any>> {
+        self.current_composition_locals().and_then(|locals| locals.get(&local).cloned())
+    }
+
+    pub(crate) fn start_providers(&mut self, values: Vec<ProvidedValue>) {
+        let parent_locals = self.current_composition_locals();
+        self.start_group(PROVIDER_KEY);
+
+        let previous = self.next_slot();
+        let has_previous = previous.is_some();
+        let previous = previous.and_then(|previous| previous.downcast::<ProviderState>().ok());
+
+        let mut provided = CompositionLocalMap::new();
+        let mut invalid = false;
+        for value in values {
+            let local = value.local();
+            let (holder, changed) = value.update(previous.as_ref().and_then(|previous| previous.provided.get(&local)));
+            invalid |= changed;
+            provided.insert(local, holder);
+        }
+
+        let mut locals = parent_locals.map(|locals| locals.deref().clone()).unwrap_or_default();
+        locals.extend(provided.iter().map(|(local, holder)| (*local, holder.clone())));
+
+        self.update_value(Rc::new(ProviderState { provided, locals: Rc::new(locals) }), has_previous);
+        self.providers_invalid_stack.push(self.providers_invalid);
+        self.providers_invalid |= invalid && !self.inserting;
+    }
+
+    pub(crate) fn end_providers(&mut self) {
+        self.providers_invalid = self.providers_invalid_stack.pop().unwrap();
+        self.end_group(PROVIDER_KEY);
     }
 
     pub(crate) fn recompose_scope(&self) -> Option<Rc<RefCell<dyn RecomposeScope>>> {
         self.invalidate_stack.last().map(|scope| scope.clone() as Rc<RefCell<dyn RecomposeScope>>)
     }
 
+    pub(crate) fn current_recompose_scope(&self) -> Option<Rc<RefCell<RecomposeScopeImpl>>> {
+        if self.derived_state_depth > 0 {
+            return None;
+        }
+        self.invalidate_stack.last().cloned()
+    }
+
+    /// record reads in the current recompose scope and invalidate the readers once a new value is applied
+    fn register_snapshot_observers(observations: &Observations) -> Vec<ObserverHandle> {
+        let read_observations = observations.clone();
+        let read_observer = Snapshot::register_global_read_observer(move |state| {
+            // every composition of the thread observes the reads, only the one composing records them
+            let Some(scope) = Composer::try_static_dispatch(|composer| {
+                if !Rc::ptr_eq(&composer.observations, &read_observations) {
+                    return None;
+                }
+                composer.current_recompose_scope()
+            }) else {
+                return;
+            };
+
+            let epoch = scope.borrow().epoch();
+            let scope = Rc::downgrade(&scope);
+
+            // a derived state is observed through its dependencies, it can not be written itself
+            let dependencies = state.dependencies();
+            let reads = if dependencies.is_empty() {
+                vec![(state.state_id(), None)]
+            } else {
+                let record_id = state.readable_record_id();
+                dependencies.iter()
+                    .map(|dependency| (dependency.state_id(), Some((state.clone(), record_id))))
+                    .collect()
+            };
+
+            let mut observations = read_observations.borrow_mut();
+            reads.into_iter().for_each(|(state_id, derived)| {
+                let observation = Observation { scope: scope.clone(), epoch, derived };
+                let observers = observations.entry(state_id).or_default();
+                if !observers.iter().any(|observer| observer.is_same(&observation)) {
+                    observers.push(observation);
+                }
+            });
+        });
+
+        let apply_observations = observations.clone();
+        let apply_observer = Snapshot::register_apply_observer(move |changed| {
+            let changed_observations = {
+                let mut observations = apply_observations.borrow_mut();
+                changed.iter()
+                    .filter_map(|state| observations.remove_entry(&state.state_id()))
+                    .collect::<Vec<_>>()
+            };
+
+            let mut unchanged = vec![];
+            changed_observations.into_iter().for_each(|(state_id, observations)| {
+                observations.into_iter().for_each(|observation| {
+                    let Some(scope) = observation.scope.upgrade() else {
+                        return;
+                    };
+
+                    // the scope has been recomposed since the read without reading the state again
+                    if scope.borrow().epoch() != observation.epoch {
+                        return;
+                    }
+
+                    // recalculating the derived state happens outside of any observations borrow, it reads states too
+                    let derived_changed = match observation.derived.as_ref() {
+                        Some((derived, record_id)) => derived.readable_record_id() != *record_id,
+                        None => true,
+                    };
+
+                    if derived_changed {
+                        scope.borrow().invalidate();
+                    } else {
+                        unchanged.push((state_id, observation));
+                    }
+                });
+            });
+
+            // the recalculation may have read other states, the derived state is observed through its current dependencies
+            let unchanged = unchanged.into_iter()
+                .flat_map(|(state_id, observation)| {
+                    let dependencies = match observation.derived.as_ref() {
+                        Some((derived, _)) => derived.dependencies().iter().map(|dependency| dependency.state_id()).collect(),
+                        None => vec![state_id],
+                    };
+                    dependencies.into_iter().map(move |state_id| (state_id, observation.clone()))
+                })
+                .collect::<Vec<_>>();
+
+            let mut observations = apply_observations.borrow_mut();
+            unchanged.into_iter().for_each(|(state_id, observation)| {
+                let observers = observations.entry(state_id).or_default();
+                if !observers.iter().any(|observer| observer.is_same(&observation)) {
+                    observers.push(observation);
+                }
+            });
+        });
+
+        vec![read_observer, apply_observer]
+    }
+
     fn update_compound_hash_enter(&mut self, hash: u64) {
         self.hash = self.hash.rotate_left(3);
         self.hash ^= hash;
         self.depth += 1;
-        println!("enter hash: {}", hash);
+        log::trace!("enter group {} at depth {}", hash, self.depth);
     }
 
     fn update_compound_hash_exit(&mut self, hash: u64) {
         self.depth -= 1;
         self.hash ^= hash;
         self.hash = self.hash.rotate_right(3);
-        println!("exit hash: {}", hash);
+        log::trace!("exit group {} at depth {}", hash, self.depth);
     }
 
     pub(crate) fn start(
         &mut self,
         key: u64,
-        object_key: Option<Box<dyn Any>>,
+        object_key: Option<Rc<dyn ObjectKey>>,
         group_kind: GroupKind,
         data: Option<Box<dyn Any>>,
     ) {
         self.validate_node_not_expected();
         self.update_compound_hash_enter(key);
+        frame_stats::record(|stats| stats.groups_composed += 1);
 
         let is_node = group_kind.is_node();
-        if self.inserting() {
-            self.read_writer.begin_empty();
-
-            if is_node {
-                self.read_writer.start_node(key, None)
-            } else if data.is_some() {
-                todo!()
+        if !self.inserting() {
+            // the reused content creates the nodes which are not reusable again
+            let group_index = if self.reusing && key == NODE_KEY {
+                None
             } else {
-                self.read_writer.start_group(key, self.depth, object_key)
+                self.read_writer.find_group(key, object_key.as_ref())
+            };
+
+            match group_index {
+                Some(group_index) => {
+                    let current_index = self.read_writer.slot_visit_index;
+                    if group_index != current_index {
+                        let from = self.node_index + self.read_writer.node_count_between(current_index, group_index);
+                        let count = self.read_writer.node_count_between(group_index, group_index + 1);
+                        self.read_writer.move_slot_to_current(group_index);
+                        self.record_move_node(from, self.node_index, count);
+                    }
+                    self.read_writer.skip_slot();
+
+                    self.enter_group(is_node);
+                    return;
+                }
+                None => {
+                    self.inserting = true;
+                }
             }
-
-            self.enter_group(is_node);
-            return;
         }
-        
-        if key == self.read_writer.group_key(self.read_writer.current_slot()) {
+
+        self.read_writer.begin_empty();
+        if is_node {
+            self.read_writer.start_node(key, None)
+        } else if data.is_some() {
             todo!()
         } else {
-            self.read_writer.begin_empty();
-            self.inserting = true;
-            if is_node {
-                self.read_writer.start_node(key, None)
-            } else if data.is_some() {
-                todo!()
-            } else {
-                self.read_writer.start_group(key, self.depth, object_key)
-            }
+            self.read_writer.start_group(key, self.depth, object_key)
         }
 
-        self.enter_group(group_kind.is_node());
+        self.enter_group(is_node);
     }
 
     fn enter_group(&mut self, is_node: bool) {
         if is_node {
             self.node_index_stack.push(self.node_index);
             self.node_index = 0;
+        } else {
+            self.group_node_index_stack.push(self.node_index);
         }
 
         self.read_writer.enter_group()
@@ -425,11 +1195,17 @@ impl ComposerImpl {
             if self.previous_remove >= 0 {
                 let remove_index = self.previous_remove;
                 self.previous_remove = -1;
-                self.record_applier_operation(move |applier, _| {
+                self.change_list.record(move |applier, _| {
                     applier.remove(remove_index as usize, count)
                 });
             } else {
-                todo!("move")
+                let from = self.previous_move_from;
+                let to = self.previous_move_to;
+                self.previous_move_from = -1;
+                self.previous_move_to = -1;
+                self.change_list.record(move |applier, _| {
+                    applier.r#move(from as usize, to as usize, count)
+                });
             }
         }
     }
@@ -446,11 +1222,98 @@ impl ComposerImpl {
         }
     }
 
+    pub(crate) fn record_move_node(&mut self, from: usize, to: usize, count: usize) {
+        if count > 0 {
+            let (from, to) = (from as i32, to as i32);
+            if self.previous_count > 0
+                && self.previous_move_from == from - self.previous_count as i32
+                && self.previous_move_to == to - self.previous_count as i32 {
+                self.previous_count += count
+            } else {
+                self.realize_movement();
+                self.previous_move_from = from;
+                self.previous_move_to = to;
+                self.previous_count = count;
+            }
+        }
+    }
+
+    fn remove_rest_of_group(&mut self) {
+        while !self.read_writer.is_group_end() {
+            let slot = self.read_writer.pop_current_slot();
+            let node_count = slot.borrow().node_count();
+            self.record_remove_node(self.node_index as i32, node_count);
+            if !self.keep_movable_contents(&slot) {
+                self.record_forgetting(&slot);
+            }
+        }
+    }
+
+    /// the observers remembered inside of `slot` are forgotten and its nodes are released once the changes are applied
+    fn record_forgetting(&mut self, slot: &Slot) {
+        let mut observers = vec![];
+        slot.borrow().visit_lifecycle_observer(&mut |observer| observers.push(observer.clone()));
+
+        let mut nodes = vec![];
+        slot.borrow().visit_node(&mut |node| nodes.push(node.clone()));
+        if observers.is_empty() && nodes.is_empty() {
+            return;
+        }
+
+        self.change_list.record(move |_, remember_manager| {
+            observers.into_iter().for_each(|observer| remember_manager.forgetting(observer));
+            nodes.into_iter().for_each(|node| remember_manager.release(node.lifecycle()));
+        });
+    }
+
+    /// the observer remembered at the next slot unless `changed`, otherwise the slot is reserved for the
+    /// observer passed to `update_remembered_observer` and the previous one is forgotten
+    pub(crate) fn remembered_observer<T: RememberObserver>(&mut self, changed: bool) -> Option<Rc<RefCell<T>>> {
+        if !self.inserting() {
+            if let Some(previous) = self.read_writer.next_observer() {
+                match previous.downcast::<RefCell<T>>() {
+                    Ok(previous) if !changed && !self.reusing => return Some(previous),
+                    _ => {
+                        let previous = self.read_writer.replace_previous_value(Rc::new(()));
+                        self.record_forgetting(&previous);
+                        return None;
+                    }
+                }
+            }
+
+            // the observer of deactivated content has been forgotten already, a placeholder keeps its slot
+            if self.read_writer.next_value().is_some() {
+                return None;
+            }
+        }
+
+        self.read_writer.update(Rc::new(()));
+        None
+    }
+
+    /// store `observer` into the slot reserved by `remembered_observer`, it is remembered once the changes are applied
+    pub(crate) fn update_remembered_observer<T: RememberObserver>(&mut self, observer: T) -> Rc<RefCell<T>> {
+        let value = observer.wrap_with_rc_refcell();
+        self.read_writer.replace_previous_observer(value.clone(), value.clone(), std::any::type_name::<T>());
+
+        let observer: Rc<RefCell<dyn RememberObserver>> = value.clone();
+        self.remembered_observers.push(observer.clone());
+        self.change_list.record(move |_, remember_manager| remember_manager.remembering(observer));
+        value
+    }
+
+    pub(crate) fn record_side_effect(&mut self, effect: impl FnOnce() + 'static) {
+        self.change_list.record(move |_, remember_manager| remember_manager.side_effects(Box::new(effect)));
+    }
+
     pub(crate) fn end(&mut self, is_node: bool) {
+        if !self.inserting() {
+            self.remove_rest_of_group();
+        }
+
         let parent = self.read_writer.parent();
         self.update_compound_hash_exit(self.read_writer.group_key(Some(parent)));
 
-        self.read_writer.end_empty();
         if is_node {
             if self.inserting() {
                 self.read_writer.end_insert_layout_node();
@@ -458,78 +1321,94 @@ impl ComposerImpl {
                 self.record_insert();
             } else {
                 self.read_writer.end_insert_layout_node();
+                self.record_applier_operation(|applier, _| {
+                    applier.up();
+                });
             }
-        } else {
-            if self.inserting() {
-                self.read_writer.end_insert_group(GroupKindIndex::Group);
+        } else if self.inserting() {
+            self.read_writer.end_insert_group(GroupKindIndex::Group);
+        }
 
-                if !self.read_writer.in_empty() {
-                    self.inserting = false;
-                }
-            }
+        self.exit_group(is_node);
 
-            let remove_index = self.read_writer.slot_visit_index as i32;
-            while !self.read_writer.is_group_end() {
-                let node_count = self.read_writer.skip_slot();
-                self.record_remove_node(remove_index as i32, node_count);
+        if self.inserting() {
+            self.read_writer.end_empty();
+            if !self.read_writer.in_empty() {
+                self.inserting = false;
             }
         }
-
-        self.exit_group(is_node);
     }
 
-    fn next_slot(&mut self) -> Option<&dyn Any> {
+    fn next_slot(&mut self) -> Option<Rc<dyn Any>> {
         if self.inserting() {
             None
         } else {
-            todo!()
+            self.read_writer.next_value()
         }
     }
 
-    fn update_value(&mut self, value: Rc<RefCell<dyn Any>>) {
-        if self.inserting() {
+    /// store `value` into the slot read by the last `next_slot`, or insert it when there was none
+    fn update_value(&mut self, value: Rc<dyn Any>, has_previous: bool) {
+        if self.inserting() || !has_previous {
             self.read_writer.update(value);
         } else {
-            todo!()
+            self.read_writer.replace_previous_value(value);
         }
     }
 
-    pub(crate) fn cache<R, T>(&mut self, key: &R, calculation: impl FnOnce() -> T) -> SnapShotValue<T>
-        where T: 'static, R: Sized + PartialEq<R> + 'static {
+    pub(crate) fn cache<R, T>(&mut self, key: &R) -> Option<SnapShotValue<T>>
+        where T: 'static, R: Sized + PartialEq<R> + Clone + 'static {
         let changed = self.changed(key);
-        if changed {
-            let value = calculation();
-            let obj = value.wrap_with_rc_refcell();
-            self.update_value(obj.clone());
-            return SnapShotValue::new(obj);
-        } else {
-            todo!()
+        let value = self.next_slot();
+        let has_previous = value.is_some();
+
+        match value.and_then(|value| value.downcast::<RefCell<T>>().ok()) {
+            Some(value) if !changed && !self.reusing => Some(SnapShotValue::new(value)),
+            _ => {
+                // reserve the slot, the calculated value is stored by `update_cached_value`
+                self.update_value(Rc::new(()), has_previous);
+                None
+            }
         }
     }
 
-    pub(crate) fn changed<T>(&mut self, key: &T) -> bool where T: Sized + PartialEq<T> + 'static {
-        if let Some(obj) = self.next_slot() {
-            if let Some(obj_cast) = obj.downcast_ref::<T>() {
-                return obj_cast != key;
-            }
+    pub(crate) fn update_cached_value<T>(&mut self, value: T) -> SnapShotValue<T> where T: 'static {
+        let obj = value.wrap_with_rc_refcell();
+        self.read_writer.replace_previous_remembered(obj.clone(), std::any::type_name::<T>());
+        SnapShotValue::new(obj)
+    }
+
+    pub(crate) fn changed<T>(&mut self, key: &T) -> bool where T: Sized + PartialEq<T> + Clone + 'static {
+        let previous = self.next_slot();
+        let has_previous = previous.is_some();
+        let changed = match previous.as_ref().and_then(|obj| obj.downcast_ref::<T>()) {
+            Some(previous) => previous != key,
+            None => true,
+        };
+
+        if changed {
+            self.update_value(Rc::new(key.clone()), has_previous);
         }
 
-        true
+        changed
     }
 
     pub(crate) fn has_pending_changes(&self) -> bool {
         !self.changes.is_empty()
     }
 
-    pub(crate) fn apply_changes(&mut self) {
-        self.composition.apply_changes();
+    pub(crate) fn apply_changes(&mut self) -> RememberEventDispatcher {
+        self.remembered_observers.clear();
+        self.change_list.apply_changes()
     }
 }
 
 impl ComposerImpl {
-    pub(crate) fn new(root: Rc<RefCell<LayoutNode>>) -> Self {
+    pub(crate) fn new(root: ComposedNode, applier: impl Applier<ApplierInType> + 'static) -> Self {
         let mut slot_table = SlotTable::default();
         let read_writer = slot_table.open_read_writer();
+        let observations = Observations::default();
+        let snapshot_observers = Self::register_snapshot_observers(&observations);
 
         Self {
             hash: 0,
@@ -537,22 +1416,44 @@ impl ComposerImpl {
             sequence: 0,
             node_expected: false,
             inserting: false,
+            reusing: false,
+            reusing_group: None,
             layout_node_stack: vec![],
             slot_table,
-            root: None,
+            root: Some(root.clone()),
             fix_up: vec![],
             insert_up_fix_up: vec![],
             changes: vec![],
             pending_stack: vec![],
             read_writer,
             invalidate_stack: vec![],
-            composition: Composition::new(UiApplier::new(root)),
+            invalidations: Invalidations::default(),
+            invalid_scopes: HashMap::new(),
+            invalid_ancestors: HashMap::new(),
+            root_content: None,
+            observations,
+            snapshot_observers,
+            derived_state_depth: 0,
+            providers_invalid: false,
+            providers_invalid_stack: vec![],
+            parent_locals: None,
+            movable_contents: HashMap::new(),
+            removed_movable_contents: HashMap::new(),
+            composed_movable_contents: HashSet::new(),
+            movable_content_inserting_stack: vec![],
+            source_names: HashMap::new(),
+            disposed: false,
+            remembered_observers: vec![],
+            change_list: ChangeList::new(applier),
 
             previous_remove: -1,
+            previous_move_from: -1,
+            previous_move_to: -1,
             previous_count: 0,
 
             node_index: 0,
             node_index_stack: vec![],
+            group_node_index_stack: vec![],
 
             pending: None,
         }
@@ -561,10 +1462,151 @@ impl ComposerImpl {
 
 impl DerivedStateObserver for ComposerImpl {
     fn start(&mut self) {
-        todo!()
+        self.derived_state_depth += 1;
     }
 
     fn done(&mut self) {
-        todo!()
+        self.derived_state_depth -= 1;
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::composition::Composition;
+    use crate::foundation::key::key;
+    use crate::foundation::test_tree::{Node, test_composition, TestNode};
+
+    thread_local! {
+        static ITEMS: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+        static TEXT: RefCell<&'static str> = RefCell::new("");
+    }
+
+    fn content() {
+        let items = ITEMS.with(|items| items.borrow().clone());
+        for item in items {
+            key(item, || Node(item, || {}));
+        }
+    }
+
+    type Children = Vec<Rc<RefCell<TestNode>>>;
+
+    /// compose `before` then `after`, returns the composition and the children of the root for each
+    fn recompose(before: &[&'static str], after: &[&'static str]) -> (Composition, Children, Children) {
+        let (composition, root) = test_composition();
+        ITEMS.with(|items| *items.borrow_mut() = before.to_vec());
+        composition.set_content(content);
+        composition.apply_changes();
+        assert_eq!(root.borrow().names(), before);
+        let first = root.borrow().children.clone();
+
+        ITEMS.with(|items| *items.borrow_mut() = after.to_vec());
+        composition.set_content(content);
+        composition.apply_changes();
+        assert_eq!(root.borrow().names(), after);
+        composition.validate_group();
+        let second = root.borrow().children.clone();
+        (composition, first, second)
+    }
+
+    fn same_node(nodes: &[Rc<RefCell<TestNode>>], name: &str, other: &[Rc<RefCell<TestNode>>]) -> bool {
+        let find = |nodes: &[Rc<RefCell<TestNode>>]| nodes.iter().find(|node| node.borrow().name == name).cloned().unwrap();
+        Rc::ptr_eq(&find(nodes), &find(other))
+    }
+
+    #[test]
+    fn keyed_insert() {
+        let (_composition, first, second) = recompose(&["a", "c"], &["x", "a", "b", "c", "y"]);
+        assert!(same_node(&first, "a", &second));
+        assert!(same_node(&first, "c", &second));
+    }
+
+    #[test]
+    fn keyed_remove() {
+        let (_composition, first, second) = recompose(&["a", "b", "c", "d"], &["b", "d"]);
+        assert!(same_node(&first, "b", &second));
+        assert!(same_node(&first, "d", &second));
+        assert!(first[0].borrow().released);
+        assert!(first[2].borrow().released);
+        assert!(!first[1].borrow().released);
+    }
+
+    #[test]
+    fn keyed_move() {
+        for after in [["d", "c", "b", "a", "e"], ["c", "d", "e", "a", "b"], ["b", "a", "e", "d", "c"], ["e", "a", "b", "c", "d"]] {
+            let (_composition, first, second) = recompose(&["a", "b", "c", "d", "e"], &after);
+            for name in after {
+                assert!(same_node(&first, name, &second), "{name} was recreated moving to {after:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn keyed_move_insert_and_remove() {
+        let (_composition, first, second) = recompose(&["a", "b", "c", "d"], &["d", "x", "b"]);
+        assert!(same_node(&first, "d", &second));
+        assert!(same_node(&first, "b", &second));
+        assert!(first[0].borrow().released);
+        assert!(first[2].borrow().released);
+    }
+
+    #[Composable]
+    fn Parsed(text: &'static str) -> Option<u32> {
+        let value = text.parse::<u32>().ok()?;
+        if value == 0 {
+            return None;
+        }
+        Node(text, || {});
+        Some(value)
+    }
+
+    #[Composable]
+    fn Shown(show: bool) {
+        if !show {
+            return;
+        }
+        Node("shown", || {});
+    }
+
+    fn early_return_content() {
+        let text = TEXT.with(|text| *text.borrow());
+        let parsed = Parsed(text);
+        Shown(parsed.is_some());
+        Node("after", || {});
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn early_return_ends_the_group() {
+        let (composition, root) = test_composition();
+        for (text, names) in [("5", vec!["5", "shown", "after"]), ("x", vec!["after"]), ("0", vec!["after"]), ("7", vec!["7", "shown", "after"])] {
+            TEXT.with(|current| *current.borrow_mut() = text);
+            composition.set_content(early_return_content);
+            composition.apply_changes();
+            composition.validate_group();
+            assert_eq!(root.borrow().names(), names);
+        }
+    }
+
+    #[Composable]
+    fn Label(text: &str, suffix: Option<&'static str>) {
+        Node(format!("{}{}", text, suffix.unwrap_or_default()), || {});
+    }
+
+    #[test]
+    fn borrowed_params_are_always_changed() {
+        let (composition, root) = test_composition();
+        for text in ["a", "b"] {
+            TEXT.with(|current| *current.borrow_mut() = text);
+            composition.set_content(|| {
+                let text = TEXT.with(|text| text.borrow().to_string());
+                Label(&text, Some("!"));
+            });
+            composition.apply_changes();
+            assert_eq!(root.borrow().names(), vec![format!("{}!", text)]);
+        }
+    }
+}
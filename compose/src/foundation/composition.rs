@@ -1,64 +1,231 @@
-use crate::foundation::composer_impl::ApplierInType;
-use std::any::Any;
 use std::cell::RefCell;
-use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
-use crate::foundation::applier::Applier;
-use crate::foundation::composer_impl::{Change, ChangeType};
+
+use crate::foundation::applier::{Applier, NodeApplier};
+use crate::foundation::compose_node::ComposedNode;
+use crate::foundation::compose_node_lifecycle_callback::ComposeNodeLifecycleCallback;
+use crate::foundation::composer::Composer;
+use crate::foundation::composer_impl::ComposerImpl;
+use crate::foundation::composition_local::CompositionLocalMap;
+use crate::foundation::inspector::InspectorGroup;
 use crate::foundation::layout_node::LayoutNode;
-use crate::foundation::remember_manager::{RememberEventDispatcher, RememberManager};
-use crate::foundation::utils::box_wrapper::WrapWithBox;
+use crate::foundation::snapshot::Snapshot;
+use crate::foundation::ui::frame_stats::{record_phase, FramePhase};
+use crate::foundation::ui_applier::UiApplier;
 
-pub(crate) struct Composition {
-    applier: Box<dyn Applier<Rc<RefCell<LayoutNode>>>>,
-    changes: Vec<Change>,
-    deferred_changes: Vec<Change>,
+/// A composition of its own content into the nodes below `root`. Each composition owns its slot
+/// table and applier, so any number of them can live side by side on one thread, they all recompose
+/// along with the thread's `Recomposer`.
+pub struct Composition {
+    composer: Rc<RefCell<ComposerImpl>>,
 }
 
 impl Composition {
-    pub(crate) fn new(applier: impl Applier<Rc<RefCell<LayoutNode>>> + 'static) -> Self {
+    /// a composition into the layout nodes below `root`, made by the owners which attach the layout nodes to
+    /// a window. other trees are composed into with `with_applier`
+    pub(crate) fn new(root: Rc<RefCell<LayoutNode>>) -> Self {
+        Self::with_applier(root.clone(), UiApplier::new(root))
+    }
+
+    /// a composition into another kind of tree, the nodes emitted by the content with `ComposeNode`
+    /// are nodes of `T` and their changes are applied to the tree by `applier`
+    pub fn with_applier<T, A>(root: Rc<RefCell<T>>, applier: A) -> Self
+        where T: ComposeNodeLifecycleCallback + 'static, A: Applier<Rc<RefCell<T>>> + 'static {
+        let root = ComposedNode::new(root);
+        let applier = NodeApplier::new(root.clone(), applier);
+        let composer = Rc::new(RefCell::new(ComposerImpl::new(root, applier)));
+        Composer::register(&composer);
         Self {
-            applier: applier.wrap_with_box(),
-            changes: Vec::new(),
-            deferred_changes: vec![],
+            composer
         }
     }
 
-    pub(crate) fn record(&mut self, action: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
-        self.changes.push(Change {
-            change: Box::new(action),
-            change_type: ChangeType::Changes,
+    /// compose `content`, the changes it produced are applied by `apply_changes`
+    pub fn set_content(&self, content: impl Fn() + 'static) {
+        assert!(!self.is_disposed(), "the content of a disposed composition can not be set");
+        let content: Rc<dyn Fn()> = Rc::new(content);
+        record_phase(FramePhase::Composition, || {
+            Composer::activate(&self.composer, move || Composer::set_active_content(content))
         });
     }
 
-    pub(crate) fn record_deferred_change(&mut self, deferred_change: impl FnOnce(&mut dyn Applier<ApplierInType>, &mut dyn RememberManager) + 'static) {
-        self.deferred_changes.push(Change {
-            change: Box::new(deferred_change),
-            change_type: ChangeType::DeferredChange,
-        });
+    /// compose `content` against the current slot table without inserting, panics if its structure differs
+    pub fn compose_validate_structure(&self, content: impl Fn()) {
+        Composer::activate(&self.composer, move || Composer::compose_active_validate_structure(content));
+    }
+
+    /// recompose the scopes invalidated since the last composition,
+    /// returns false if there was nothing to recompose
+    pub fn recompose(&self) -> bool {
+        Snapshot::send_apply_notifications();
+        Composer::activate(&self.composer, Composer::recompose_active)
+    }
+
+    pub fn apply_changes(&self) {
+        Composer::activate(&self.composer, Composer::apply_active_changes);
+    }
+
+    pub fn apply_deferred_changes(&self) {
+        Composer::activate(&self.composer, Composer::apply_active_deferred_changes);
+    }
+
+    pub fn has_invalidations(&self) -> bool {
+        Snapshot::send_apply_notifications();
+        self.composer.borrow().has_invalidations()
+    }
+
+    /// make `locals` visible to the content, used by compositions created from within another composition
+    pub(crate) fn set_parent_locals(&self, locals: Option<Rc<CompositionLocalMap>>) {
+        self.composer.borrow_mut().set_parent_locals(locals);
+    }
+
+    /// the groups of the content and the layout nodes they emitted, as they were composed last
+    pub fn inspect(&self) -> Vec<InspectorGroup> {
+        self.composer.borrow().inspect()
+    }
+
+    /// forget everything the content remembered, last remembered first, which cancels its launched
+    /// effects, then remove its nodes from the root and release them, does nothing the second time
+    pub fn dispose(&self) {
+        Composer::activate(&self.composer, Composer::dispose_active);
+    }
+
+    pub fn is_disposed(&self) -> bool {
+        self.composer.borrow().is_disposed()
     }
 
-    pub(crate) fn apply_changes(&mut self) {
-        let mut changes = Vec::<Change>::new();
-        std::mem::swap(&mut self.changes, &mut changes);
+    pub fn validate_group(&self) {
+        self.composer.borrow().validate_group();
+    }
+}
+
+impl Drop for Composition {
+    fn drop(&mut self) {
+        // no observer runs while unwinding, the slots are cleared only
+        if std::thread::panicking() {
+            self.composer.borrow_mut().destroy();
+        } else {
+            self.dispose();
+        }
+    }
+}
 
-        self.applier.on_begin_changes();
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
 
-        let mut remember_dispatcher = RememberEventDispatcher::new();
-        changes.into_iter().for_each(|change| {
-            (change.change)(self.applier.deref_mut(), &mut remember_dispatcher);
+    use crate::foundation::composition::Composition;
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::memory::leak_report::assert_no_node_leaks;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::remember::remember_observer;
+    use crate::foundation::remember_observer::RememberObserver;
+    use crate::foundation::test_tree::{Node, test_composition};
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = RefCell::new(vec![]);
+        static COUNT: Cell<usize> = Cell::new(0);
+    }
+
+    struct Observer(u32);
+
+    impl RememberObserver for Observer {
+        fn on_remembered(&self) {
+            EVENTS.with(|events| events.borrow_mut().push(format!("remembered {}", self.0)));
+        }
+
+        fn on_forgotten(&self) {
+            EVENTS.with(|events| events.borrow_mut().push(format!("forgotten {}", self.0)));
+        }
+
+        fn on_abandoned(&self) {}
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+    }
+
+    fn content() {
+        remember_observer(&1, || Observer(1));
+        BoxLayout(Modifier, |_| {
+            remember_observer(&2, || Observer(2));
+            BoxLayout(Modifier, |_| {});
         });
+        BoxLayout(Modifier, |_| {});
+    }
 
-        self.applier.on_end_changes();
+    #[test]
+    fn dispose_forgets_observers_and_removes_nodes() {
+        assert_no_node_leaks(|| {
+            let root = LayoutNode::new();
+            let composition = Composition::new(root.clone());
+            composition.set_content(content);
+            composition.apply_changes();
+            assert_eq!(take_events(), vec!["remembered 1", "remembered 2"]);
+            assert_eq!(root.borrow().children.borrow().len(), 2);
+
+            let child = root.borrow().children.borrow()[0].clone();
+            composition.dispose();
+            assert_eq!(take_events(), vec!["forgotten 2", "forgotten 1"]);
+            assert!(root.borrow().children.borrow().is_empty());
+            assert!(child.borrow().get_parent().is_none());
+            assert!(child.borrow().children.borrow().is_empty());
+            drop(child);
+
+            composition.dispose();
+            drop(composition);
+            assert!(take_events().is_empty());
+        });
     }
 
-    pub(crate) fn apply_deferred_changes(&mut self) {
-        let mut deferred_changes = Vec::<Change>::new();
-        std::mem::swap(&mut self.deferred_changes, &mut deferred_changes);
+    #[test]
+    fn drop_disposes() {
+        assert_no_node_leaks(|| {
+            let root = LayoutNode::new();
+            let composition = Composition::new(root.clone());
+            composition.set_content(content);
+            composition.apply_changes();
+            take_events();
+
+            drop(composition);
+            assert_eq!(take_events(), vec!["forgotten 2", "forgotten 1"]);
+            assert!(root.borrow().children.borrow().is_empty());
+        });
+    }
 
-        let mut remember_dispatcher = RememberEventDispatcher::new();
-        deferred_changes.into_iter().for_each(|change| {
-            (change.change)(self.applier.deref_mut(), &mut remember_dispatcher);
+    fn tree_content() {
+        let count = COUNT.with(|count| count.get());
+        Node("list", || {
+            for index in 0..count {
+                Node(format!("item {index}"), || {});
+            }
         });
+        Node("footer", || {});
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn with_applier_composes_into_another_tree() {
+        let (composition, root) = test_composition();
+        let list_names = || root.borrow().children[0].borrow().names();
+
+        COUNT.with(|count| count.set(2));
+        composition.set_content(tree_content);
+        composition.apply_changes();
+        assert_eq!(root.borrow().names(), ["list", "footer"]);
+        assert_eq!(list_names(), ["item 0", "item 1"]);
+        let list = root.borrow().children[0].clone();
+
+        COUNT.with(|count| count.set(1));
+        composition.set_content(tree_content);
+        composition.apply_changes();
+        composition.validate_group();
+        assert_eq!(root.borrow().names(), ["list", "footer"]);
+        assert_eq!(list_names(), ["item 0"]);
+        assert!(std::rc::Rc::ptr_eq(&list, &root.borrow().children[0]));
+
+        composition.dispose();
+        assert!(root.borrow().children.is_empty());
+        assert!(list.borrow().released);
+    }
+}
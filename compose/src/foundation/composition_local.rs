@@ -0,0 +1,163 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::foundation::composer::Composer;
+use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompositionLocalKind {
+    /// the provided value is kept in a state, only its readers recompose when it changes
+    Dynamic,
+    /// the provided value is kept as is, the whole content of the provider recomposes when it changes
+    Static,
+}
+
+/// the id of the next local used for the first time, 0 marks a local without an id yet
+static NEXT_LOCAL_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// A value provided to the composition below a `CompositionLocalProvider`, read with `current`.
+/// Locals are declared as `static` items, a `const` one would be a new local on every use and does not compile.
+pub struct CompositionLocal<T> {
+    default_factory: fn() -> T,
+    kind: CompositionLocalKind,
+    /// assigned on first use, the constructors are `const` and cannot draw it from `NEXT_LOCAL_ID`
+    id: AtomicUsize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+pub const fn composition_local_of<T>(default_factory: fn() -> T) -> CompositionLocal<T> {
+    CompositionLocal {
+        default_factory,
+        kind: CompositionLocalKind::Dynamic,
+        id: AtomicUsize::new(0),
+        _marker: PhantomData,
+    }
+}
+
+/// for values that rarely change, reading them is not tracked
+pub const fn static_composition_local_of<T>(default_factory: fn() -> T) -> CompositionLocal<T> {
+    CompositionLocal {
+        default_factory,
+        kind: CompositionLocalKind::Static,
+        id: AtomicUsize::new(0),
+        _marker: PhantomData,
+    }
+}
+
+pub(crate) type CompositionLocalId = usize;
+
+/// the holder of every local provided above a group, a `MutableState<T>` for dynamic locals and a `T` for static ones
+pub(crate) type CompositionLocalMap = HashMap<CompositionLocalId, Rc<dyn Any>>;
+
+pub struct ProvidedValue {
+    local: CompositionLocalId,
+    /// returns the holder of the value, reusing `previous` if possible, and whether the content needs to recompose
+    update: Box<dyn FnOnce(Option<&Rc<dyn Any>>) -> (Rc<dyn Any>, bool)>,
+}
+
+impl ProvidedValue {
+    pub(crate) fn local(&self) -> CompositionLocalId {
+        self.local
+    }
+
+    pub(crate) fn update(self, previous: Option<&Rc<dyn Any>>) -> (Rc<dyn Any>, bool) {
+        (self.update)(previous)
+    }
+}
+
+impl<T: 'static> CompositionLocal<T> {
+    fn id(&'static self) -> CompositionLocalId {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+
+        let next_id = NEXT_LOCAL_ID.fetch_add(1, Ordering::Relaxed);
+        match self.id.compare_exchange(0, next_id, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => next_id,
+            Err(id) => id,
+        }
+    }
+
+    pub fn provides(&'static self, value: T) -> ProvidedValue where T: PartialEq + Clone {
+        let update: Box<dyn FnOnce(Option<&Rc<dyn Any>>) -> (Rc<dyn Any>, bool)> = match self.kind {
+            CompositionLocalKind::Dynamic => Box::new(move |previous| {
+                match previous.and_then(|previous| previous.clone().downcast::<MutableState<T>>().ok()) {
+                    Some(state) => {
+                        if *state.borrow() != value {
+                            state.set(value);
+                        }
+                        (state, false)
+                    }
+                    None => (Rc::new(mutable_state_of(value)), false),
+                }
+            }),
+            CompositionLocalKind::Static => Box::new(move |previous| {
+                match previous {
+                    Some(previous) if previous.downcast_ref::<T>() == Some(&value) => (previous.clone(), false),
+                    _ => (Rc::new(value), true),
+                }
+            }),
+        };
+
+        ProvidedValue {
+            local: self.id(),
+            update,
+        }
+    }
+
+    /// the value provided by the nearest `CompositionLocalProvider` above, or the default value
+    pub fn current(&'static self) -> T where T: Clone {
+        let Some(holder) = Composer::composition_local(self.id()) else {
+            return (self.default_factory)();
+        };
+
+        match self.kind {
+            CompositionLocalKind::Dynamic => holder.downcast_ref::<MutableState<T>>().unwrap().get(),
+            CompositionLocalKind::Static => holder.downcast_ref::<T>().unwrap().clone(),
+        }
+    }
+}
+
+/// Provide `values` to `content` and everything it composes.
+pub fn CompositionLocalProvider(values: Vec<ProvidedValue>, content: impl FnOnce()) {
+    Composer::start_providers(values);
+    content();
+    Composer::end_providers();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::foundation::composition_local::{composition_local_of, static_composition_local_of, CompositionLocal, CompositionLocalProvider};
+    use crate::foundation::test_tree::test_composition;
+
+    static LOCAL_FIRST: CompositionLocal<u32> = composition_local_of(|| 0);
+    static LOCAL_SECOND: CompositionLocal<u32> = static_composition_local_of(|| 0);
+
+    thread_local! {
+        static READS: RefCell<Vec<(u32, u32, u32)>> = RefCell::new(vec![]);
+    }
+
+    #[test]
+    fn locals_are_told_apart_by_their_id() {
+        let leaked: &'static CompositionLocal<u32> = Box::leak(Box::new(composition_local_of(|| 5)));
+        let read = move || READS.with(|reads| reads.borrow_mut().push((LOCAL_FIRST.current(), LOCAL_SECOND.current(), leaked.current())));
+
+        let (composition, _root) = test_composition();
+        composition.set_content(move || {
+            read();
+            CompositionLocalProvider(vec![LOCAL_FIRST.provides(1)], || {
+                read();
+                CompositionLocalProvider(vec![LOCAL_SECOND.provides(2), leaked.provides(3)], read);
+            });
+        });
+        composition.apply_changes();
+
+        assert_eq!(READS.with(|reads| reads.take()), vec![(0, 0, 5), (1, 0, 5), (1, 2, 3)]);
+    }
+}
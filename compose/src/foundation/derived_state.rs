@@ -1,4 +1,252 @@
+use std::cell::{Cell, Ref, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use crate::foundation::composer::Composer;
+use crate::foundation::snapshot::{Snapshot, SnapshotId, StateObject, StateObjectId};
+use crate::foundation::snapshot::snapshot_impl::{current_snapshot, notify_read};
+
+/// Notified around the calculation of a derived state, reads in between are dependencies of the
+/// derived state rather than reads of whoever is observing.
 pub(crate) trait DerivedStateObserver {
     fn start(&mut self);
     fn done(&mut self);
-}
\ No newline at end of file
+}
+
+struct DerivedStateImpl<T> {
+    id: StateObjectId,
+    calculation: Box<dyn Fn() -> T>,
+    value: RefCell<Option<T>>,
+    /// bumped whenever a calculation produces a different value
+    version: Cell<SnapshotId>,
+    dependencies: RefCell<Vec<(Rc<dyn StateObject>, SnapshotId)>>,
+}
+
+impl<T: PartialEq + 'static> DerivedStateImpl<T> {
+    fn is_valid(&self) -> bool {
+        self.value.borrow().is_some() && self.dependencies.borrow().iter()
+            .all(|(dependency, record_id)| dependency.readable_record_id() == *record_id)
+    }
+
+    fn update(&self) {
+        if self.is_valid() {
+            return;
+        }
+
+        let reads = Rc::new(RefCell::new(Vec::<Rc<dyn StateObject>>::new()));
+        let capture = reads.clone();
+        let handle = Snapshot::register_global_read_observer(move |state| {
+            let mut reads = capture.borrow_mut();
+            if reads.iter().all(|read| read.state_id() != state.state_id()) {
+                reads.push(state.clone());
+            }
+        });
+        let value = Composer::observe_derived_state(|| (self.calculation)());
+        handle.dispose();
+
+        let dependencies = reads.take().into_iter()
+            .map(|dependency| {
+                let record_id = dependency.readable_record_id();
+                (dependency, record_id)
+            })
+            .collect();
+        self.dependencies.replace(dependencies);
+
+        let changed = self.value.borrow().as_ref() != Some(&value);
+        if changed {
+            self.value.replace(Some(value));
+            self.version.set(self.version.get() + 1);
+        }
+    }
+}
+
+impl<T: PartialEq + 'static> StateObject for DerivedStateImpl<T> {
+    fn state_id(&self) -> StateObjectId {
+        self.id
+    }
+
+    fn readable_record_id(&self) -> SnapshotId {
+        self.update();
+        self.version.get()
+    }
+
+    /// the states read by the last calculation, a derived state read by it stands for its own dependencies
+    /// so that only states which are written end up here
+    fn dependencies(&self) -> Vec<Rc<dyn StateObject>> {
+        let mut dependencies: Vec<Rc<dyn StateObject>> = vec![];
+        self.dependencies.borrow().iter().for_each(|(dependency, _)| {
+            let nested = dependency.dependencies();
+            let states = if nested.is_empty() { vec![dependency.clone()] } else { nested };
+            states.into_iter().for_each(|state| {
+                if dependencies.iter().all(|dependency| dependency.state_id() != state.state_id()) {
+                    dependencies.push(state);
+                }
+            });
+        });
+        dependencies
+    }
+}
+
+/// A state whose value is calculated from other states. The value is cached and only recalculated
+/// once a state read by the last calculation changed, readers are only invalidated when the result differs.
+pub struct DerivedState<T> {
+    state: Rc<DerivedStateImpl<T>>,
+}
+
+pub fn derived_state_of<T: PartialEq + 'static>(calculation: impl Fn() -> T + 'static) -> DerivedState<T> {
+    DerivedState {
+        state: Rc::new(DerivedStateImpl {
+            id: StateObjectId::next(),
+            calculation: Box::new(calculation),
+            value: RefCell::new(None),
+            version: Cell::new(0),
+            dependencies: RefCell::new(vec![]),
+        })
+    }
+}
+
+impl<T: PartialEq + 'static> DerivedState<T> {
+    pub fn get(&self) -> T where T: Clone {
+        self.borrow().clone()
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        self.state.update();
+        notify_read(&current_snapshot(), &(self.state.clone() as Rc<dyn StateObject>));
+        Ref::map(self.state.value.borrow(), |value| value.as_ref().unwrap())
+    }
+
+    pub fn state_id(&self) -> StateObjectId {
+        self.state.id
+    }
+}
+
+impl<T> Clone for DerivedState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone()
+        }
+    }
+}
+
+impl<T> PartialEq for DerivedState<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl<T: Debug> Debug for DerivedState<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DerivedState")
+            .field("value", &self.state.value.borrow())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::foundation::composition::Composition;
+    use crate::foundation::derived_state::{derived_state_of, DerivedState};
+    use crate::foundation::mutable_state::mutable_state_of;
+    use crate::foundation::snapshot::Snapshot;
+    use crate::foundation::test_tree::test_composition;
+
+    /// a composition whose content reads `state`, along with the number of times the content ran
+    fn read_in_composition<T: PartialEq + Clone + 'static>(state: &DerivedState<T>) -> (Composition, Rc<Cell<usize>>) {
+        let runs = Rc::new(Cell::new(0));
+        let (composition, _root) = test_composition();
+        let (state, counter) = (state.clone(), runs.clone());
+        composition.set_content(move || {
+            counter.set(counter.get() + 1);
+            state.get();
+        });
+        composition.apply_changes();
+        (composition, runs)
+    }
+
+    #[test]
+    fn calculates_once_read_and_again_after_a_dependency_changed() {
+        let calculations = Rc::new(Cell::new(0));
+        let items = mutable_state_of(vec![1, 2]);
+        let sum = {
+            let (items, calculations) = (items.clone(), calculations.clone());
+            derived_state_of(move || {
+                calculations.set(calculations.get() + 1);
+                items.get().iter().sum::<i32>()
+            })
+        };
+        assert_eq!(calculations.get(), 0);
+
+        assert_eq!((sum.get(), sum.get()), (3, 3));
+        assert_eq!(calculations.get(), 1);
+
+        items.update(|items| items.push(3));
+        Snapshot::send_apply_notifications();
+        assert_eq!(calculations.get(), 1);
+        assert_eq!(sum.get(), 6);
+        assert_eq!(calculations.get(), 2);
+    }
+
+    #[test]
+    fn readers_are_invalidated_only_when_the_value_changed() {
+        let items = mutable_state_of(vec![1, 2]);
+        let evens = {
+            let items = items.clone();
+            derived_state_of(move || items.get().into_iter().filter(|item| item % 2 == 0).collect::<Vec<_>>())
+        };
+        let (composition, runs) = read_in_composition(&evens);
+
+        items.update(|items| items.push(3));
+        assert!(!composition.recompose());
+        assert_eq!(runs.get(), 1);
+
+        items.update(|items| items.push(4));
+        assert!(composition.recompose());
+        assert_eq!(runs.get(), 2);
+        assert_eq!(evens.get(), vec![2, 4]);
+    }
+
+    #[test]
+    fn readers_of_a_nested_derived_state_are_invalidated() {
+        let count = mutable_state_of(1);
+        let doubled = {
+            let count = count.clone();
+            derived_state_of(move || count.get() * 2)
+        };
+        let quadrupled = {
+            let doubled = doubled.clone();
+            derived_state_of(move || doubled.get() * 2)
+        };
+        // the inner state is cached before the outer one reads it
+        assert_eq!(doubled.get(), 2);
+        let (composition, runs) = read_in_composition(&quadrupled);
+
+        count.set(2);
+        assert!(composition.recompose());
+        assert_eq!(runs.get(), 2);
+        assert_eq!(quadrupled.get(), 8);
+    }
+
+    #[test]
+    fn readers_observe_the_dependencies_of_the_last_calculation() {
+        let use_first = mutable_state_of(false);
+        let first = mutable_state_of(1);
+        let second = mutable_state_of(1);
+        let selected = {
+            let (use_first, first, second) = (use_first.clone(), first.clone(), second.clone());
+            derived_state_of(move || if use_first.get() { first.get() } else { second.get() })
+        };
+        let (composition, runs) = read_in_composition(&selected);
+
+        // the value stays the same, the readers now depend on `first` instead of `second`
+        use_first.set(true);
+        assert!(!composition.recompose());
+
+        first.set(2);
+        assert!(composition.recompose());
+        assert_eq!(runs.get(), 2);
+        assert_eq!(selected.get(), 2);
+    }
+}
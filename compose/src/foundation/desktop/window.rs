@@ -3,17 +3,20 @@ use std::cell::RefCell;
 use std::fmt::Display;
 use crate::foundation::geometry::{Density, IntRect};
 use crate::foundation::canvas::Canvas;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use crate as compose;
 use minifb::{Key, KeyRepeat, Scale, ScaleMode, Window, WindowOptions};
 use skia_safe::{AlphaType, ColorSpace, ColorType, ImageInfo, surfaces,
 };
+use std::path::PathBuf;
 use std::rc::Rc;
 use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
 use crate::foundation::composer::Composer;
 use crate::foundation::drawing::canvas_impl::new_canvas;
 use crate::foundation::geometry::IntSize;
 use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+use crate::foundation::recomposer::Recomposer;
+use crate::foundation::saveable::saveable_state_registry::SaveableStateRegistry;
 use crate::foundation::ui::compose_scene::ComposeScene;
 use crate::foundation::ui::graphics::color::Color;
 use crate::foundation::utils::result_extension::ResultExtension;
@@ -23,6 +26,8 @@ pub struct DesktopWindowOption {
     visible: bool,
     title: String,
     size: IntSize,
+    saved_state_file: Option<PathBuf>,
+    trace_file: Option<PathBuf>,
 }
 
 impl Default for DesktopWindowOption {
@@ -32,12 +37,29 @@ impl Default for DesktopWindowOption {
             visible: true,
             title: "Untitled".to_string(),
             size: IntSize::new(800, 500),
+            saved_state_file: None,
+            trace_file: None,
         }
     }
 }
 
+impl DesktopWindowOption {
+    /// restore the values of `remember_saveable` from the JSON file at `path` when the window opens,
+    /// they are saved to it once the window is closed
+    pub fn saved_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.saved_state_file = Some(path.into());
+        self
+    }
+
+    /// trace the frames of the window into a Chrome trace event file at `path`, written once the window is closed
+    pub fn trace_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.trace_file = Some(path.into());
+        self
+    }
+}
+
 pub fn DesktopWindow(option: DesktopWindowOption,
-                     content: impl Fn(),
+                     content: impl Fn() + 'static,
                      diff: impl Fn()) {
     let window_width = option.size.width;
     let window_height = option.size.height;
@@ -74,16 +96,27 @@ pub fn DesktopWindow(option: DesktopWindowOption,
 
     let mut canvas = new_canvas(surface.canvas());
     let mut compose_view_rc = SkiaBaseOwner::new(IntRect::ZERO);
-    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
     let mut redraw_need = Rc::new(RefCell::new(true));
     let redraw_capture = redraw_need.clone();
     let mut compose_scene = ComposeScene::new(runtime, Density::default(), Box::new(move || {
         redraw_capture.replace(true);
     }));
     compose_scene.attach(compose_view_rc.clone());
+    if option.trace_file.is_some() {
+        compose_scene.start_trace();
+    }
+
+    let saveable_state_registry = option.saved_state_file.as_ref().map(|path| {
+        SaveableStateRegistry::load(path).unwrap_or_else(|error| {
+            log::warn!("failed to restore the saved state from {}: {}", path.display(), error);
+            SaveableStateRegistry::new(Default::default())
+        })
+    });
 
     let mut compose_view = compose_view_rc.borrow_mut();
 
+    compose_view.set_saveable_state_registry(saveable_state_registry.clone());
     compose_view.set_content(content);
     drop(compose_view);
 
@@ -99,8 +132,15 @@ pub fn DesktopWindow(option: DesktopWindowOption,
 
     Composer::debug_print();
 
+    let mut recomposer = Recomposer::new();
     while windows.is_open() && !windows.is_key_pressed(Key::Escape, KeyRepeat::No) {
-        {
+        let frame_start = Instant::now();
+        compose_scene.run_dispatched();
+        compose_scene.record_frame(|| {
+            if recomposer.run_frame() {
+                redraw_need.replace(true);
+            }
+
             let mut compose_view = compose_view_rc.borrow_mut();
             let (width, height) = windows.get_size();
             compose_view.update_bound(IntRect::new(0, 0, width as i32, height as i32));
@@ -116,11 +156,26 @@ pub fn DesktopWindow(option: DesktopWindowOption,
                 canvas.clear(Color::WHITE);
                 compose_view.dispatch_draw(&mut canvas);
             }
-        }
+        });
+        compose_scene.finish_frame();
 
         windows.update_with_buffer(buffer.as_slice(), window_width, window_height).unwrap();
         process_mouse_event(&mut compose_scene, &windows);
-        std::thread::sleep(Duration::from_millis(66));
+        compose_scene.dispatch_coroutines();
+        // the posted work runs while the rest of the frame budget is waited out
+        compose_scene.wait_for_dispatched(Duration::from_millis(66).saturating_sub(frame_start.elapsed()));
+    }
+
+    if let (Some(path), Some(registry)) = (option.saved_state_file.as_ref(), saveable_state_registry) {
+        if let Err(error) = registry.save(path) {
+            log::warn!("failed to save the state to {}: {}", path.display(), error);
+        }
+    }
+
+    if let Some(path) = option.trace_file.as_ref() {
+        if let Err(error) = compose_scene.stop_trace(path) {
+            log::warn!("failed to write the trace to {}: {}", path.display(), error);
+        }
     }
 
     compose_scene.detach(compose_view_rc.clone());
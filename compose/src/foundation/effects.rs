@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+
+use tokio::task::JoinHandle;
+
+use crate::foundation::composer::Composer;
+use crate::foundation::remember_observer::RememberObserver;
+use crate::foundation::ui::compose_scene::{CoroutineTasks, LOCAL_COROUTINE_TASKS};
+
+pub struct DisposableEffectScope {
+    _private: (),
+}
+
+pub struct DisposableEffectResult {
+    on_dispose: Box<dyn FnOnce()>,
+}
+
+impl DisposableEffectScope {
+    /// `on_dispose` runs once the effect leaves the composition or before it runs again for new keys
+    pub fn on_dispose(&self, on_dispose: impl FnOnce() + 'static) -> DisposableEffectResult {
+        DisposableEffectResult {
+            on_dispose: Box::new(on_dispose)
+        }
+    }
+}
+
+struct DisposableEffectImpl {
+    effect: RefCell<Option<Box<dyn FnOnce(&DisposableEffectScope) -> DisposableEffectResult>>>,
+    on_dispose: RefCell<Option<DisposableEffectResult>>,
+}
+
+impl RememberObserver for DisposableEffectImpl {
+    fn on_remembered(&self) {
+        if let Some(effect) = self.effect.take() {
+            let result = effect(&DisposableEffectScope { _private: () });
+            self.on_dispose.replace(Some(result));
+        }
+    }
+
+    fn on_forgotten(&self) {
+        if let Some(result) = self.on_dispose.take() {
+            (result.on_dispose)();
+        }
+    }
+
+    fn on_abandoned(&self) {}
+}
+
+struct LaunchedEffectImpl {
+    tasks: Option<CoroutineTasks>,
+    future: RefCell<Option<Pin<Box<dyn Future<Output=()>>>>>,
+    job: RefCell<Option<JoinHandle<()>>>,
+}
+
+impl RememberObserver for LaunchedEffectImpl {
+    fn on_remembered(&self) {
+        let Some(future) = self.future.take() else {
+            return;
+        };
+
+        match self.tasks.as_ref() {
+            Some(tasks) => {
+                self.job.replace(Some(tasks.launch(future)));
+            }
+            None => log::warn!("LaunchedEffect is composed outside of an owner attached to a ComposeScene, it is never launched"),
+        }
+    }
+
+    fn on_forgotten(&self) {
+        if let Some(job) = self.job.take() {
+            job.abort();
+        }
+    }
+
+    fn on_abandoned(&self) {}
+}
+
+/// Run `effect` every time the composition that called it is applied.
+pub fn SideEffect(effect: impl FnOnce() + 'static) {
+    Composer::record_side_effect(effect)
+}
+
+/// Run `effect` once it enters the composition. The result of `on_dispose` runs when it leaves the
+/// composition, or before `effect` runs again because `keys` changed.
+pub fn DisposableEffect<K>(keys: &K, effect: impl FnOnce(&DisposableEffectScope) -> DisposableEffectResult + 'static)
+    where K: PartialEq + Clone + 'static {
+    let changed = Composer::changed(keys);
+    Composer::remember_observer(changed, move || DisposableEffectImpl {
+        effect: RefCell::new(Some(Box::new(effect))),
+        on_dispose: RefCell::new(None),
+    });
+}
+
+/// Launch `future` on the `ComposeScene` the owner of the composition is attached to, once it
+/// enters the composition. It is cancelled when it leaves the composition, or relaunched with the
+/// future passed along with changed `keys`.
+pub fn LaunchedEffect<K>(keys: &K, future: impl Future<Output=()> + 'static)
+    where K: PartialEq + Clone + 'static {
+    launch_effect(keys, move || future);
+}
+
+/// like `LaunchedEffect`, the future is only created when it is going to be launched
+pub(crate) fn launch_effect<K, F>(keys: &K, future: impl FnOnce() -> F)
+    where K: PartialEq + Clone + 'static, F: Future<Output=()> + 'static {
+    let changed = Composer::changed(keys);
+    let tasks = LOCAL_COROUTINE_TASKS.current();
+    Composer::remember_observer(changed, move || LaunchedEffectImpl {
+        tasks,
+        future: RefCell::new(Some(Box::pin(future()))),
+        job: RefCell::new(None),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::effects::{DisposableEffect, LaunchedEffect, SideEffect};
+    use crate::foundation::geometry::{Density, IntRect};
+    use crate::foundation::ui::compose_scene::ComposeScene;
+
+    thread_local! {
+        static KEY: Cell<u32> = Cell::new(0);
+        static SHOW: Cell<bool> = Cell::new(true);
+        static EVENTS: RefCell<Vec<String>> = RefCell::new(vec![]);
+    }
+
+    fn record(event: String) {
+        EVENTS.with(|events| events.borrow_mut().push(event));
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.with(|events| events.take())
+    }
+
+    fn reset() {
+        KEY.with(|key| key.set(0));
+        SHOW.with(|show| show.set(true));
+        take_events();
+    }
+
+    fn scene() -> ComposeScene {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        ComposeScene::new(runtime, Density::default(), Box::new(|| {}))
+    }
+
+    struct Cancelled(u32);
+
+    impl Drop for Cancelled {
+        fn drop(&mut self) {
+            record(format!("cancelled {}", self.0));
+        }
+    }
+
+    fn side_effect_content() {
+        SideEffect(|| record("side effect".to_string()));
+    }
+
+    fn disposable_effect_content() {
+        if SHOW.with(Cell::get) {
+            let key = KEY.with(Cell::get);
+            DisposableEffect(&key, move |scope| {
+                record(format!("effect {}", key));
+                scope.on_dispose(move || record(format!("dispose {}", key)))
+            });
+        }
+    }
+
+    fn launched_effect_content() {
+        if SHOW.with(Cell::get) {
+            let key = KEY.with(Cell::get);
+            LaunchedEffect(&key, async move {
+                let _cancelled = Cancelled(key);
+                record(format!("launch {}", key));
+                std::future::pending::<()>().await;
+            });
+        }
+    }
+
+    #[test]
+    fn side_effect_runs_after_apply() {
+        reset();
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        let owner = owner.borrow();
+        let composition = owner.composition();
+
+        owner.set_content(side_effect_content);
+        assert!(take_events().is_empty());
+        composition.apply_changes();
+        assert_eq!(take_events(), vec!["side effect"]);
+    }
+
+    #[test]
+    fn disposable_effect_disposes_on_key_change_and_removal() {
+        reset();
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        let owner = owner.borrow();
+        let composition = owner.composition();
+
+        owner.set_content(disposable_effect_content);
+        composition.apply_changes();
+        assert_eq!(take_events(), vec!["effect 0"]);
+
+        owner.set_content(disposable_effect_content);
+        composition.apply_changes();
+        assert!(take_events().is_empty());
+
+        KEY.with(|key| key.set(1));
+        owner.set_content(disposable_effect_content);
+        composition.apply_changes();
+        assert_eq!(take_events(), vec!["dispose 0", "effect 1"]);
+
+        SHOW.with(|show| show.set(false));
+        owner.set_content(disposable_effect_content);
+        composition.apply_changes();
+        assert_eq!(take_events(), vec!["dispose 1"]);
+    }
+
+    #[test]
+    fn launched_effect_is_cancelled_on_key_change_and_removal() {
+        reset();
+        let mut scene = scene();
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        scene.attach(owner.clone());
+        let owner = owner.borrow();
+        let composition = owner.composition();
+
+        owner.set_content(launched_effect_content);
+        composition.apply_changes();
+        scene.dispatch_coroutines();
+        assert_eq!(take_events(), vec!["launch 0"]);
+
+        KEY.with(|key| key.set(1));
+        owner.set_content(launched_effect_content);
+        composition.apply_changes();
+        scene.dispatch_coroutines();
+        assert_eq!(take_events(), vec!["cancelled 0", "launch 1"]);
+
+        SHOW.with(|show| show.set(false));
+        owner.set_content(launched_effect_content);
+        composition.apply_changes();
+        scene.dispatch_coroutines();
+        assert_eq!(take_events(), vec!["cancelled 1"]);
+    }
+
+    #[test]
+    fn launched_effects_run_on_the_scene_of_their_owner() {
+        reset();
+        let mut first_scene = scene();
+        let mut second_scene = scene();
+        let first_owner = SkiaBaseOwner::new(IntRect::ZERO);
+        first_scene.attach(first_owner.clone());
+        let second_owner = SkiaBaseOwner::new(IntRect::ZERO);
+        second_scene.attach(second_owner.clone());
+
+        let first_owner = first_owner.borrow();
+        first_owner.set_content(launched_effect_content);
+        first_owner.composition().apply_changes();
+        second_scene.dispatch_coroutines();
+        assert!(take_events().is_empty());
+        first_scene.dispatch_coroutines();
+        assert_eq!(take_events(), vec!["launch 0"]);
+
+        KEY.with(|key| key.set(1));
+        let second_owner = second_owner.borrow();
+        second_owner.set_content(launched_effect_content);
+        second_owner.composition().apply_changes();
+        first_scene.dispatch_coroutines();
+        assert!(take_events().is_empty());
+        second_scene.dispatch_coroutines();
+        assert_eq!(take_events(), vec!["launch 1"]);
+    }
+}
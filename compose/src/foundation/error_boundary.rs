@@ -0,0 +1,362 @@
+use std::any::Any;
+use std::fmt::{Display, Formatter};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::rc::Rc;
+
+use auto_delegate::Delegate;
+use compose_foundation_macro::ModifierElement;
+use compose_macro::Composable;
+
+use crate as compose;
+use crate::foundation::composer::Composer;
+use crate::foundation::composer_impl::ERROR_BOUNDARY_FALLBACK_KEY;
+use crate::foundation::constraint::Constraints;
+use crate::foundation::measurable::{Measurable, MultiChildrenMeasurePolicy, MultiChildrenMeasurePolicyDelegate};
+use crate::foundation::measure_scope::{empty_place_action, MeasureScope, MeasureScopeLayoutAction};
+use crate::foundation::modifier::{Modifier, ModifierNodeElement, ModifierNodeImpl};
+use crate::foundation::modifier_node::DrawModifierNode;
+use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+use crate::foundation::placement_scope::PlacementScope;
+use crate::foundation::remember::remember;
+use crate::foundation::ui::align::Alignment;
+use crate::foundation::ui::draw::ContentDrawScope;
+use crate::widgets::layout::Layout;
+use crate::widgets::r#box::remember_box_measure_policy;
+
+/// the pass of the content of an `ErrorBoundary` which panicked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPhase {
+    Composition,
+    Measure,
+    Layout,
+    Draw,
+}
+
+/// a panic caught by an `ErrorBoundary`
+#[derive(Debug)]
+pub struct BoundaryError {
+    phase: ErrorPhase,
+    message: String,
+}
+
+impl BoundaryError {
+    pub fn phase(&self) -> ErrorPhase {
+        self.phase
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for BoundaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "panicked during {:?}: {}", self.phase, self.message)
+    }
+}
+
+/// identifies the content group of an error boundary, and receives the panics of its content
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ErrorBoundaryHandle {
+    error: MutableState<Option<Rc<BoundaryError>>>,
+}
+
+impl ErrorBoundaryHandle {
+    /// keep the first panic of the content, the boundary composes its fallback from now on
+    pub(crate) fn report(&self, phase: ErrorPhase, panic: Box<dyn Any + Send>) -> Rc<BoundaryError> {
+        let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        log::error!("error boundary caught a panic during {:?}: {}", phase, message);
+
+        let error = Rc::new(BoundaryError { phase, message });
+        self.error.set(Some(error.clone()));
+        error
+    }
+}
+
+/// Compose `content`, or `fallback` once a panic was caught during the composition, measure, layout or
+/// draw of `content`. The slots and node changes of a composition which panicked are rolled back, so
+/// the rest of the composition carries on as if `content` was never composed, and the remember
+/// observers created by it are abandoned. Note that the slots of `content` are copied every time it is
+/// composed or recomposed to be able to roll them back, which costs about as much as composing all of
+/// `content` again. Keep boundaries around the parts which may panic rather than around whole screens.
+#[Composable]
+pub fn ErrorBoundary(fallback: impl Fn(&BoundaryError), content: impl Fn()) {
+    let error = remember(&(), || mutable_state_of(None::<Rc<BoundaryError>>)).borrow().clone();
+    let boundary = ErrorBoundaryHandle { error: error.clone() };
+
+    let modifier = Modifier.then(error_boundary_draw_element(boundary.clone()));
+    Layout(modifier, error_boundary_measure_policy(boundary.clone()), || {
+        let caught = error.get().or_else(|| {
+            Composer::compose_error_boundary(boundary.clone(), &content).err()
+                .map(|panic| boundary.report(ErrorPhase::Composition, panic))
+        });
+
+        if let Some(caught) = caught {
+            Composer::start_group(ERROR_BOUNDARY_FALLBACK_KEY);
+            fallback(&caught);
+            Composer::end_group(ERROR_BOUNDARY_FALLBACK_KEY);
+        }
+    });
+}
+
+/// lays out the content like a `BoxLayout`, the panics of measuring and placing it are reported to `boundary`
+fn error_boundary_measure_policy(boundary: ErrorBoundaryHandle) -> MultiChildrenMeasurePolicy {
+    let content_measure_policy = remember_box_measure_policy(Alignment::TOP_START, false);
+
+    MultiChildrenMeasurePolicyDelegate(move |measure_scope: &dyn MeasureScope,
+                                             measurables: &mut [&mut dyn Measurable],
+                                             constraints: &Constraints| {
+        let measured = catch_unwind(AssertUnwindSafe(|| {
+            (&mut *content_measure_policy.borrow_mut())(measure_scope, measurables, constraints)
+        }));
+
+        match measured {
+            Ok(measure_result) => {
+                let place_action = measure_result.placement_block();
+                let boundary = boundary.clone();
+                measure_scope.layout(measure_result.as_int_size(), move |scope: &dyn PlacementScope| {
+                    let Some(place_action) = place_action.as_ref() else {
+                        return;
+                    };
+                    if let Err(panic) = catch_unwind(AssertUnwindSafe(|| place_action(scope))) {
+                        boundary.report(ErrorPhase::Layout, panic);
+                    }
+                })
+            }
+            Err(panic) => {
+                boundary.report(ErrorPhase::Measure, panic);
+                measure_scope.layout((constraints.min_width, constraints.min_height), empty_place_action)
+            }
+        }
+    })
+}
+
+fn error_boundary_draw_element(boundary: ErrorBoundaryHandle) -> Modifier {
+    let update_boundary = boundary.clone();
+    ModifierNodeElement(
+        "ErrorBoundary",
+        move || {
+            ErrorBoundaryDrawNode {
+                boundary: boundary.clone(),
+                node_impl: ModifierNodeImpl::default(),
+            }
+        },
+        move |node: &mut ErrorBoundaryDrawNode| {
+            node.boundary = update_boundary.clone();
+        },
+    )
+}
+
+#[derive(Debug, ModifierElement, Delegate)]
+#[Impl(Draw)]
+struct ErrorBoundaryDrawNode {
+    boundary: ErrorBoundaryHandle,
+
+    #[to(ModifierNode)]
+    node_impl: ModifierNodeImpl,
+}
+
+impl DrawModifierNode for ErrorBoundaryDrawNode {
+    fn draw(&self, draw_scope: &mut dyn ContentDrawScope) {
+        let save_count = draw_scope.get_draw_context_mut().get_canvas().save_count();
+        if let Err(panic) = catch_unwind(AssertUnwindSafe(|| draw_scope.draw_content())) {
+            // the content may have panicked between a save and its restore
+            let canvas = draw_scope.get_draw_context_mut().get_canvas();
+            while canvas.save_count() > save_count {
+                canvas.restore();
+            }
+            self.boundary.report(ErrorPhase::Draw, panic);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use compose_macro::Composable;
+    use skia_safe::canvas::SaveLayerRec;
+    use skia_safe::{Point, Rect, scalar};
+
+    use crate as compose;
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::canvas::Canvas;
+    use crate::foundation::composer::Composer;
+    use crate::foundation::error_boundary::{BoundaryError, ErrorBoundary, ErrorPhase};
+    use crate::foundation::geometry::IntRect;
+    use crate::foundation::measurable::MultiChildrenMeasurePolicyDelegate;
+    use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+    use crate::foundation::recomposer::Recomposer;
+    use crate::foundation::remember::{remember, remember_observer};
+    use crate::foundation::remember_observer::RememberObserver;
+    use crate::foundation::ui::graphics::color::Color;
+    use crate::widgets::layout::Layout;
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = RefCell::new(vec![]);
+        static BROKEN: MutableState<bool> = mutable_state_of(false);
+    }
+
+    fn log(event: String) {
+        EVENTS.with(|events| events.borrow_mut().push(event));
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+    }
+
+    struct Observer(&'static str);
+
+    impl RememberObserver for Observer {
+        fn on_remembered(&self) {
+            log(format!("remembered {}", self.0));
+        }
+
+        fn on_forgotten(&self) {
+            log(format!("forgotten {}", self.0));
+        }
+
+        fn on_abandoned(&self) {
+            log(format!("abandoned {}", self.0));
+        }
+    }
+
+    fn fallback(error: &BoundaryError) {
+        // the fallback is recomposed along with its boundary, log the error once
+        remember(&(), || log(format!("{:?}: {}", error.phase(), error.message())));
+        BoxLayout(Modifier, |_| {});
+    }
+
+    fn compose_measure_and_draw(owner: &Rc<RefCell<SkiaBaseOwner>>) {
+        Composer::apply_changes();
+        Composer::apply_deferred_changes();
+        MeasureLayoutDeferActionManager::with_manager(|defer_measure, defer_layout| {
+            owner.borrow_mut().dispatch_measure(300, 200);
+            defer_measure();
+            owner.borrow_mut().dispatch_layout();
+            defer_layout();
+        });
+        owner.borrow_mut().dispatch_draw(&mut TestCanvas::default());
+    }
+
+    #[Composable]
+    fn Content() {
+        remember_observer(&(), || Observer("content"));
+        if BROKEN.with(|broken| broken.get()) {
+            remember_observer(&(), || Observer("broken"));
+            panic!("composition broke");
+        }
+        BoxLayout(Modifier, |_| {});
+    }
+
+    #[test]
+    fn panic_in_composition_abandons_observers() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(|| {
+            ErrorBoundary(fallback, || {
+                remember_observer(&(), || Observer("initial"));
+                panic!("composition broke");
+            });
+        });
+        compose_measure_and_draw(&owner);
+        assert_eq!(take_events(), vec!["abandoned initial", "Composition: composition broke"]);
+        Composer::validate_group();
+    }
+
+    #[test]
+    fn panic_in_recomposition_abandons_observers() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(|| {
+            // the sibling is placed again without being measured again
+            BoxLayout(Modifier, |_| {});
+            ErrorBoundary(fallback, Content);
+        });
+        compose_measure_and_draw(&owner);
+        assert_eq!(take_events(), vec!["remembered content"]);
+
+        BROKEN.with(|broken| broken.set(true));
+        Recomposer::new().run_until_idle(3);
+        compose_measure_and_draw(&owner);
+        assert_eq!(take_events(), vec![
+            "abandoned broken",
+            "Composition: composition broke",
+            "forgotten content",
+        ]);
+        Composer::validate_group();
+    }
+
+    #[test]
+    fn panic_in_measure_composes_fallback() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(|| {
+            ErrorBoundary(fallback, || {
+                Layout(Modifier, MultiChildrenMeasurePolicyDelegate(|_, _, _| panic!("measure broke")), || {});
+            });
+        });
+        compose_measure_and_draw(&owner);
+        Recomposer::new().run_until_idle(3);
+        compose_measure_and_draw(&owner);
+        assert_eq!(take_events(), vec!["Measure: measure broke"]);
+    }
+
+    #[test]
+    fn panic_in_draw_composes_fallback() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(|| {
+            ErrorBoundary(fallback, || {
+                BoxLayout(Modifier.background(Color::RED), |_| {});
+            });
+        });
+        compose_measure_and_draw(&owner);
+        Recomposer::new().run_until_idle(3);
+        compose_measure_and_draw(&owner);
+        assert_eq!(take_events(), vec!["Draw: draw broke"]);
+    }
+
+    /// a canvas which panics when it draws a rect
+    #[derive(Default)]
+    struct TestCanvas {
+        save_count: Cell<usize>,
+    }
+
+    impl Canvas for TestCanvas {
+        fn save(&mut self) {
+            self.save_count.set(self.save_count.get() + 1);
+        }
+
+        fn restore(&mut self) {
+            self.save_count.set(self.save_count.get() - 1);
+        }
+
+        fn save_layer(&mut self) -> SaveLayerRec {
+            self.save();
+            SaveLayerRec::default()
+        }
+
+        fn save_layer_alpha(&mut self, _rect: Option<Rect>, _alpha: f32) {
+            self.save();
+        }
+
+        fn save_count(&self) -> usize {
+            self.save_count.get()
+        }
+
+        fn translate(&mut self, _x: f32, _y: f32) {}
+
+        fn scale(&mut self, _x: f32, _y: f32) {}
+
+        fn draw_circle(&mut self, _point: Point, _scalar: scalar, _color: Color) {}
+
+        fn draw_rect(&mut self, _color: Color, _rect: Rect) {
+            panic!("draw broke");
+        }
+
+        fn clear(&mut self, _color: Color) {}
+    }
+}
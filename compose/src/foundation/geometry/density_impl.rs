@@ -1,4 +1,4 @@
-use super::{Density, Dp};
+use super::{Density, Dp, IntoDp};
 
 impl Density {
     pub fn new(density: f64, font_scale: f64) -> Self {
@@ -12,6 +12,14 @@ impl Density {
         dp.value * self.density
     }
 
+    pub fn px_to_dp(&self, px: usize) -> Dp {
+        if px == usize::MAX {
+            Dp::INFINITE
+        } else {
+            (px as f64 / self.density).dp()
+        }
+    }
+
     pub fn dp_round_to_px(&self, dp: Dp) -> usize {
         let px = self.dp_to_px(dp);
 
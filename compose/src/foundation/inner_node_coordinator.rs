@@ -16,7 +16,7 @@ use crate::foundation::layout::layout_coordinates::LayoutCoordinates;
 use crate::foundation::layout_node::LayoutNode;
 use crate::foundation::layout_node_layout_delegate::LayoutNodeLayoutDelegate;
 use crate::foundation::measurable::{Measurable, MultiChildrenMeasurePolicyDelegate};
-use crate::foundation::measurable::MultiChildrenMeasurePolicy;
+use crate::foundation::measurable::{MultiChildrenMeasurePolicy, SubcomposeNodeMeasurePolicy};
 use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
 use crate::foundation::measure_pass_delegate::MeasurePassDelegate;
 use crate::foundation::measure_result::{MeasureResult, MeasureResultProvider};
@@ -55,6 +55,8 @@ pub(crate) struct InnerNodeCoordinator {
     pub(crate) node_coordinator_impl: NodeCoordinatorImpl,
     pub(crate) layout_node: Weak<RefCell<LayoutNode>>,
     pub(crate) measure_policy: MultiChildrenMeasurePolicy,
+    /// replaces `measure_policy` for nodes whose children are composed while measuring
+    pub(crate) subcompose_measure_policy: Option<SubcomposeNodeMeasurePolicy>,
     pub(crate) measure_pass_delegate: Weak<RefCell<MeasurePassDelegate>>,
 
     weak_this: Weak<RefCell<Self>>,
@@ -85,6 +87,7 @@ impl InnerNodeCoordinator {
     pub(crate) fn new() -> Rc<RefCell<InnerNodeCoordinator>> {
         let mut result = InnerNodeCoordinator {
             measure_policy: error_measure_policy(),
+            subcompose_measure_policy: None,
             layout_node: Weak::new(),
             node_coordinator_impl: NodeCoordinatorImpl::new(),
             measure_pass_delegate: Weak::new(),
@@ -116,6 +119,10 @@ impl InnerNodeCoordinator {
         self.measure_policy = measure_policy;
     }
 
+    pub(crate) fn set_subcompose_measure_policy(&mut self, measure_policy: SubcomposeNodeMeasurePolicy) {
+        self.subcompose_measure_policy = Some(measure_policy);
+    }
+
     pub(crate) fn on_measured(&self) {
         println!("child {:p} measured {:?}\n", self, self.get_measured_size());
     }
@@ -182,7 +189,12 @@ impl Measurable for InnerNodeCoordinator {
                     .set_measured_by_parent(UsageByParent::NotUsed)
             });
 
-            let measure_result = {
+            let measure_result = if let Some(subcompose_measure_policy) = this.subcompose_measure_policy.clone() {
+                // the policy composes the children itself, they can not be borrowed up front
+                let measure_scope = &this.node_coordinator_impl;
+                let mut subcompose_measure_policy = subcompose_measure_policy.borrow_mut();
+                subcompose_measure_policy(measure_scope, constraint)
+            } else {
                 let mut measure_policy = this.measure_policy.borrow_mut();
 
                 let children_rc = this.layout_node.upgrade().unwrap().borrow().get_children();
@@ -190,6 +202,7 @@ impl Measurable for InnerNodeCoordinator {
 
                 let layout_node_layout_delegate_rc = children
                     .iter()
+                    .filter(|child| !child.borrow().deactivated)
                     .map(|child| child.borrow_mut().layout_node_layout_delegate.clone())
                     .collect::<Vec<_>>();
                 let mut layout_node_layout_delegate_ref_mut: Vec<RefMut<LayoutNodeLayoutDelegate>> = layout_node_layout_delegate_rc
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::foundation::compose_node::ComposedNode;
+use crate::foundation::layout_node::LayoutNode;
+use crate::foundation::slot_table_type::{GroupKind, Slot};
+
+/// a group of a composition as seen by the inspector, along with the groups started inside of it
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectorGroup {
+    /// the composable function which started the group, `None` for the groups of the runtime
+    pub name: Option<&'static str>,
+    pub key: u64,
+    /// the type names of the values remembered directly in this group
+    pub remembered: Vec<&'static str>,
+    /// the layout node emitted by this group
+    pub node: Option<InspectorNode>,
+    pub children: Vec<InspectorGroup>,
+}
+
+/// a layout node as it was measured and placed last
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectorNode {
+    pub id: u32,
+    /// the names of the modifier elements attached to the node, outermost first
+    pub modifiers: Vec<&'static str>,
+    pub width: usize,
+    pub height: usize,
+    pub x: i32,
+    pub y: i32,
+    pub z_index: f32,
+}
+
+impl InspectorGroup {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    fn write_indented(&self, f: &mut Formatter<'_>, indent: usize) -> std::fmt::Result {
+        write!(f, "{:indent$}{} #{}", "", self.name.unwrap_or("<group>"), self.key, indent = indent)?;
+        if !self.remembered.is_empty() {
+            write!(f, " remember [{}]", self.remembered.join(", "))?;
+        }
+        if let Some(node) = &self.node {
+            write!(f, " {node}")?;
+        }
+        writeln!(f)?;
+
+        self.children.iter().try_for_each(|child| child.write_indented(f, indent + 2))
+    }
+}
+
+impl Display for InspectorGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+impl Display for InspectorNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LayoutNode({}) {}x{} at ({}, {}) z {}", self.id, self.width, self.height, self.x, self.y, self.z_index)?;
+        if !self.modifiers.is_empty() {
+            write!(f, " modifiers [{}]", self.modifiers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// the groups in `slots`, the values which are not inside of a group are left out
+pub(crate) fn inspect_slots(slots: &[Slot], source_names: &HashMap<u64, &'static str>) -> Vec<InspectorGroup> {
+    slots.iter().filter_map(|slot| inspect_group(&slot.borrow(), source_names)).collect()
+}
+
+fn inspect_group(group: &GroupKind, source_names: &HashMap<u64, &'static str>) -> Option<InspectorGroup> {
+    let key = group.key()?;
+    let slot_data = group.slot_data()?;
+    let node = match group {
+        GroupKind::Node { node: Some(node), .. } => inspect_node(node),
+        _ => None,
+    };
+
+    let mut remembered = vec![];
+    let mut children = vec![];
+    for slot in slot_data.borrow().iter() {
+        match &*slot.borrow() {
+            GroupKind::Remembered { type_name, .. } | GroupKind::LifecycleObserver { type_name, .. } => remembered.push(*type_name),
+            child => children.extend(inspect_group(child, source_names)),
+        }
+    }
+
+    Some(InspectorGroup {
+        name: source_names.get(&key).copied(),
+        key,
+        remembered,
+        node,
+        children,
+    })
+}
+
+fn inspect_node(node: &ComposedNode) -> Option<InspectorNode> {
+    let node = node.downcast::<LayoutNode>()?;
+    let node = node.borrow();
+
+    let modifiers = node.node_chain.borrow().modifier_container.borrow().current.iter()
+        .filter_map(|modifier| modifier.element_name())
+        .collect();
+    let outer_coordinator = node.get_outer_coordinator();
+    let outer_coordinator = outer_coordinator.borrow();
+    let size = outer_coordinator.get_measured_size();
+    let position = outer_coordinator.get_position();
+
+    Some(InspectorNode {
+        id: node.identify,
+        modifiers,
+        width: size.width,
+        height: size.height,
+        x: position.x,
+        y: position.y,
+        z_index: node.get_measure_pass_delegate().borrow().z_index,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::composition::Composition;
+    use crate::foundation::inspector::InspectorGroup;
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::remember::remember;
+    use crate::widgets::r#box::BoxLayout;
+
+    #[Composable]
+    fn Counter() {
+        let _count = remember(&(), || 3u32);
+        BoxLayout(Modifier, |_| {});
+    }
+
+    fn find<'a>(groups: &'a [InspectorGroup], name: &str) -> Option<&'a InspectorGroup> {
+        groups.iter().find_map(|group| match group.name == Some(name) {
+            true => Some(group),
+            false => find(&group.children, name),
+        })
+    }
+
+    fn has_node(group: &InspectorGroup) -> bool {
+        group.node.is_some() || group.children.iter().any(has_node)
+    }
+
+    #[test]
+    fn inspect_groups_and_nodes() {
+        let composition = Composition::new(LayoutNode::new());
+        composition.set_content(Counter);
+        composition.apply_changes();
+
+        let groups = composition.inspect();
+        let counter = find(&groups, "Counter").unwrap();
+        assert_eq!(find(&counter.children, "remember").unwrap().remembered, vec!["u32"]);
+        assert!(has_node(find(&counter.children, "BoxLayout").unwrap()));
+
+        let text = groups[0].to_string();
+        assert!(text.contains(&format!("Counter #{}", counter.key)), "{text}");
+        assert!(text.contains("remember [u32]"), "{text}");
+        assert!(text.contains("LayoutNode("), "{text}");
+    }
+
+    #[test]
+    fn inspect_to_json() {
+        let composition = Composition::new(LayoutNode::new());
+        composition.set_content(Counter);
+        composition.apply_changes();
+
+        let groups = composition.inspect();
+        let json: serde_json::Value = serde_json::from_str(&groups[0].to_json().unwrap()).unwrap();
+        let counter = &json["children"][0];
+        assert_eq!(counter["name"], "Counter");
+        assert_eq!(counter["key"], find(&groups, "Counter").unwrap().key);
+        assert_eq!(counter["children"][0]["name"], "remember");
+        assert_eq!(counter["children"][0]["remembered"], serde_json::json!(["u32"]));
+
+        let layout = &counter["children"][1]["children"][0];
+        assert_eq!(layout["name"], "Layout");
+        assert_eq!(layout["children"][0]["node"]["width"], 0);
+        assert_eq!(layout["children"][0]["node"]["modifiers"], serde_json::json!([]));
+    }
+}
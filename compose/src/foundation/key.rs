@@ -0,0 +1,157 @@
+use std::panic::Location;
+use std::rc::Rc;
+
+use crate::foundation::composer::Composer;
+
+// keys below are reserved for the composer
+const RESERVED_KEYS: u64 = 1000;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Compose `content` in a group identified by `value`. When the children of a parent are reordered,
+/// the group with an equal value is moved along with its layout nodes and remembered values
+/// instead of being recreated.
+#[track_caller]
+pub fn key<K, R>(value: K, content: impl FnOnce() -> R) -> R where K: PartialEq + 'static {
+    let hash = caller_key();
+
+    Composer::start_movable_group(hash, Rc::new(value));
+    let result = content();
+    Composer::end_movable_group();
+    result
+}
+
+/// a group key identifying the source location of the caller
+#[track_caller]
+pub(crate) fn caller_key() -> u64 {
+    let location = Location::caller();
+    location_key(location.file(), location.line(), location.column())
+}
+
+/// a group key identifying the source location of the call of the composable whose own key is `hash`
+#[track_caller]
+pub(crate) fn call_site_key(hash: u64) -> u64 {
+    outside_reserved_keys(hash ^ caller_key())
+}
+
+const fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        index += 1;
+    }
+    hash
+}
+
+/// the group key of a composable declared at `location` of the module `module_path`, used by the `Composable` macro
+#[doc(hidden)]
+pub const fn composable_key(module_path: &str, location: &str) -> u64 {
+    let mut hash = fnv1a(module_path.as_bytes(), FNV_OFFSET_BASIS);
+    hash = fnv1a(b"::", hash);
+    hash = fnv1a(location.as_bytes(), hash);
+    outside_reserved_keys(hash)
+}
+
+/// the group key of `file:line:column`, it stays the same across builds and runs so that it can key
+/// saved state
+const fn location_key(file: &str, line: u32, column: u32) -> u64 {
+    let mut hash = fnv1a(file.as_bytes(), FNV_OFFSET_BASIS);
+    hash = fnv1a(&line.to_le_bytes(), hash);
+    hash = fnv1a(&column.to_le_bytes(), hash);
+    outside_reserved_keys(hash)
+}
+
+const fn outside_reserved_keys(mut hash: u64) -> u64 {
+    while hash < RESERVED_KEYS {
+        hash = fnv1a(&hash.to_le_bytes(), FNV_OFFSET_BASIS);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use crate::foundation::composition::Composition;
+    use crate::foundation::key::{caller_key, composable_key, key, location_key, RESERVED_KEYS};
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::remember::remember;
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static ITEMS: RefCell<Vec<u32>> = RefCell::new(vec![]);
+        static CREATED: Cell<u32> = Cell::new(0);
+        static SEEN: RefCell<Vec<(u32, u32)>> = RefCell::new(vec![]);
+    }
+
+    // every item remembers the order it was created in, so a value created again is told apart
+    fn keyed_items() {
+        for id in ITEMS.with(|items| items.borrow().clone()) {
+            key(id, || {
+                let created = remember(&(), || CREATED.with(|created| created.replace(created.get() + 1)));
+                SEEN.with(|seen| seen.borrow_mut().push((id, *created.borrow())));
+                BoxLayout(Modifier, |_| {});
+            });
+        }
+    }
+
+    fn compose_items(composition: &Composition, items: Vec<u32>) -> Vec<(u32, u32)> {
+        ITEMS.with(|current| *current.borrow_mut() = items);
+        composition.set_content(keyed_items);
+        composition.apply_changes();
+        composition.validate_group();
+        SEEN.with(|seen| seen.take())
+    }
+
+    fn node_ids(root: &Rc<RefCell<LayoutNode>>) -> Vec<u32> {
+        root.borrow().children.borrow().iter().map(|child| child.borrow().identify).collect()
+    }
+
+    #[test]
+    fn composable_keys_tell_apart_modules() {
+        let location = "src/lib.rs:10:8:Content";
+
+        assert_eq!(composable_key("app", location), composable_key("app", location));
+        assert_ne!(composable_key("app", location), composable_key("widgets", location));
+        assert!(composable_key("", "") >= RESERVED_KEYS);
+    }
+
+    #[test]
+    fn caller_keys_follow_the_location() {
+        let keys = (0..2).map(|_| caller_key()).collect::<Vec<_>>();
+        assert_eq!(keys[0], keys[1]);
+        assert_ne!(keys[0], caller_key());
+
+        assert_eq!(location_key("src/lib.rs", 10, 8), location_key("src/lib.rs", 10, 8));
+        assert_ne!(location_key("src/lib.rs", 10, 8), location_key("src/lib.rs", 10, 9));
+        assert_ne!(location_key("src/lib.rs", 10, 8), location_key("src/main.rs", 10, 8));
+    }
+
+    #[test]
+    fn caller_keys_are_stable() {
+        // saved state is keyed by these, they must not change between builds
+        const KEY: u64 = location_key("src/lib.rs", 10, 8);
+        assert_eq!(KEY, 0x316c52ce420344b8);
+    }
+
+    #[test]
+    fn remembered_values_follow_reordered_keys() {
+        let root = LayoutNode::new();
+        let composition = Composition::new(root.clone());
+
+        assert_eq!(compose_items(&composition, vec![1, 2, 3]), vec![(1, 0), (2, 1), (3, 2)]);
+        let nodes = node_ids(&root);
+
+        assert_eq!(compose_items(&composition, vec![3, 1, 2]), vec![(3, 2), (1, 0), (2, 1)]);
+        assert_eq!(node_ids(&root), vec![nodes[2], nodes[0], nodes[1]]);
+
+        assert_eq!(compose_items(&composition, vec![2, 4, 3]), vec![(2, 1), (4, 3), (3, 2)]);
+        let reordered = node_ids(&root);
+        assert_eq!((reordered[0], reordered[2]), (nodes[1], nodes[2]));
+        assert!(!nodes.contains(&reordered[1]));
+    }
+}
@@ -213,7 +213,7 @@ impl PlaceablePlaceAt for LayoutModifierNodeCoordinator {
             })
         });
 
-        self.get_measured_result().take().then(|mut measure_result| {
+        self.get_measured_result().then(|measure_result| {
             measure_result.place_children(self)
         })
     }
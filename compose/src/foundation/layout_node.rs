@@ -1,8 +1,10 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
 use std::ops::Deref;
 use std::rc::{Rc, Weak};
 use std::sync::atomic::AtomicU32;
+
+use compose_foundation_macro::Leak;
 use crate::foundation::compose_node_lifecycle_callback::ComposeNodeLifecycleCallback;
 
 use crate::foundation::geometry::{Density, Offset};
@@ -23,7 +25,7 @@ use crate::foundation::utils::self_reference::SelfReference;
 
 use super::{layout_state::LayoutState, node_chain::NodeChain};
 use super::canvas::Canvas;
-use super::measurable::MultiChildrenMeasurePolicy;
+use super::measurable::{MultiChildrenMeasurePolicy, SubcomposeNodeMeasurePolicy};
 use super::modifier::Modifier;
 use super::remeasurable::StatefulRemeasurable;
 
@@ -31,6 +33,7 @@ thread_local! {
     static IDENTIFY: AtomicU32 = AtomicU32::new(0);
 }
 
+#[Leak]
 #[derive(Debug)]
 pub(crate) struct LayoutNode {
     pub(crate) layout_node_container: Rc<RefCell<LayoutNodeContainer>>,
@@ -42,12 +45,15 @@ pub(crate) struct LayoutNode {
     pub(crate) usage_by_parent: UsageByParent,
     pub(crate) layout_state: Rc<RefCell<LayoutState>>,
     pub(crate) layout_direction: LayoutDirection,
+    pub(crate) density: Density,
 
     pub(crate) owner: Option<Weak<RefCell<dyn Owner>>>,
     pub(crate) deactivated: bool,
     pub(crate) identify: u32,
 
     pub(crate) view_configuration: ViewConfiguration,
+    /// set while a subcompose layout inserts and removes children during its own measure
+    pub(crate) ignore_remeasure_requests: Cell<bool>,
 
     pub(crate) weak_self: Weak<RefCell<Self>>,
 }
@@ -70,12 +76,15 @@ impl LayoutNode {
             usage_by_parent: UsageByParent::NotUsed,
             layout_state: LayoutState::Idle.wrap_with_rc_refcell(),
             layout_direction: LayoutDirection::default(),
+            density: Density::default(),
 
             view_configuration: ViewConfiguration::default(),
+            ignore_remeasure_requests: Cell::new(false),
             owner: None,
             deactivated: false,
             weak_self: Weak::default(),
             identify: IDENTIFY.with(|identity| identity.fetch_add(1, std::sync::atomic::Ordering::SeqCst)),
+            leak_object: Default::default(),
         };
 
         let node = node.wrap_with_rc_refcell();
@@ -122,7 +131,10 @@ impl LayoutNode {
         )));
 
         self.owner = Some(owner.clone());
-        owner.upgrade().unwrap().borrow().on_attach(self);
+        // nodes composed by a subcompose layout are attached while the owner is busy measuring
+        if let Ok(owner) = owner.upgrade().unwrap().try_borrow() {
+            owner.on_attach(self);
+        }
 
         self.for_each_child(|child| {
             child.borrow_mut().attach(Some(self), owner.clone());
@@ -173,8 +185,12 @@ impl LayoutNode {
         left.borrow().get_measure_pass_delegate().borrow().z_index.partial_cmp(&right.borrow().get_measure_pass_delegate().borrow().z_index).unwrap()
     }
 
+    /// the children which are drawn and hit tested, in z order, deactivated children are left out
     pub(crate) fn z_sort_children(&self) -> Vec<Rc<RefCell<LayoutNode>>> {
-        let mut result = self.children.borrow().clone();
+        let mut result = self.children.borrow().iter()
+            .filter(|child| !child.borrow().deactivated)
+            .cloned()
+            .collect::<Vec<_>>();
         result.sort_by(Self::z_comparator);
         result
     }
@@ -215,6 +231,16 @@ impl LayoutNode {
             .inner_coordinator
             .borrow_mut()
             .set_measure_policy(measure_policy);
+        self.request_remeasure();
+    }
+
+    pub(crate) fn set_subcompose_measure_policy(&self, measure_policy: SubcomposeNodeMeasurePolicy) {
+        self.node_chain
+            .borrow()
+            .inner_coordinator
+            .borrow_mut()
+            .set_subcompose_measure_policy(measure_policy);
+        self.request_remeasure();
     }
 
     pub(crate) fn get_outer_coordinator(&self) -> Rc<RefCell<dyn NodeCoordinator>> {
@@ -277,27 +303,76 @@ impl LayoutNode {
         self.layout_node_layout_delegate
             .borrow_mut()
             .update_parent_data();
+        self.request_remeasure();
     }
 
     pub(crate) fn get_density(&self) -> Density {
-        Density::new(
-            1.0,
-            1.0,
-        )
+        self.density
+    }
+
+    pub(crate) fn set_density(&mut self, density: Density) {
+        if self.density != density {
+            self.density = density;
+            self.request_remeasure();
+        }
+    }
+
+    pub(crate) fn set_view_configuration(&mut self, view_configuration: ViewConfiguration) {
+        self.view_configuration = view_configuration;
     }
 
     pub(crate) fn get_parent(&self) -> Option<Weak<RefCell<LayoutNode>>> {
         self.node_chain.borrow().parent.clone()
     }
 
-    pub(crate) fn request_remeasure(&self) {}
+    pub(crate) fn request_remeasure(&self) {
+        if self.ignore_remeasure_requests.get() {
+            return;
+        }
+        self.get_measure_pass_delegate().borrow_mut().mark_measure_pending();
+        if let Some(parent) = self.get_parent().and_then(|parent| parent.upgrade()) {
+            parent.borrow().request_remeasure();
+        }
+    }
 
     pub(crate) fn remove_at(&mut self, index: usize, count: usize) {
         let mut children = self.children.borrow_mut();
-        for i in (index + count - 1)..=(index) {
+        for i in (index..index + count).rev() {
             let child = children.remove(i);
             self.on_remove_child(&child);
         }
+        drop(children);
+
+        self.request_remeasure();
+    }
+
+    /// remove `this` from the children of its parent, if it has one
+    pub(crate) fn remove_from_parent(this: &Rc<RefCell<LayoutNode>>) {
+        let Some(parent) = this.borrow().get_parent().and_then(|parent| parent.upgrade()) else {
+            return;
+        };
+
+        let index = parent.borrow().children.borrow().iter().position(|child| Rc::ptr_eq(child, this));
+        if let Some(index) = index {
+            parent.borrow_mut().remove_at(index, 1);
+        }
+    }
+
+    pub(crate) fn move_at(&mut self, from: usize, to: usize, count: usize) {
+        if from == to {
+            return;
+        }
+
+        // moving forward, `to` is the index in the children before the moved ones are taken out
+        let mut children = self.children.borrow_mut();
+        for i in 0..count {
+            let (from_index, to_index) = if from > to { (from + i, to + i) } else { (from, to - 1) };
+            let child = children.remove(from_index);
+            children.insert(to_index, child);
+        }
+        drop(children);
+
+        self.request_remeasure();
     }
 
     pub(crate) fn remove_all(&mut self) {
@@ -330,17 +405,59 @@ impl LayoutNode {
             }
             _ => {}
         }
+
+        self.request_remeasure();
     }
 }
 
 impl ComposeNodeLifecycleCallback for LayoutNode {
     fn on_reuse(&mut self) {
         self.deactivated = false;
+        if self.is_attached() {
+            self.node_chain.borrow_mut().mark_as_attached();
+        }
+        self.request_remeasure();
     }
 
+    /// the node stays in the tree but takes no part in measuring, drawing and hit testing, its
+    /// modifiers are set again by the content reusing it
     fn on_deactivate(&mut self) {
         self.deactivated = true;
+        self.node_chain.borrow_mut().mark_as_detached();
+        self.set_modifier(Modifier);
     }
 
-    fn on_release(&mut self) {}
+    fn on_release(&mut self) {
+        // a released node is never inserted again, nothing may stay attached through it
+        self.node_chain.borrow_mut().mark_as_detached();
+        self.remove_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::foundation::layout_node::LayoutNode;
+
+    fn move_children(from: usize, to: usize, count: usize) -> Vec<usize> {
+        let parent = LayoutNode::new();
+        let children = (0..6).map(|_| LayoutNode::new()).collect::<Vec<_>>();
+        children.iter().enumerate().for_each(|(index, child)| parent.borrow_mut().insert_at(index, child.clone()));
+
+        parent.borrow_mut().move_at(from, to, count);
+        let moved = parent.borrow().children.borrow().clone();
+        moved.iter().map(|child| children.iter().position(|node| std::rc::Rc::ptr_eq(node, child)).unwrap()).collect()
+    }
+
+    #[test]
+    fn move_forward() {
+        assert_eq!(move_children(0, 5, 2), vec![2, 3, 4, 0, 1, 5]);
+        assert_eq!(move_children(1, 6, 3), vec![0, 4, 5, 1, 2, 3]);
+        assert_eq!(move_children(2, 3, 1), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn move_backward() {
+        assert_eq!(move_children(4, 0, 2), vec![4, 5, 0, 1, 2, 3]);
+        assert_eq!(move_children(3, 1, 1), vec![0, 3, 1, 2, 4, 5]);
+    }
 }
@@ -3,6 +3,7 @@ use std::rc::Rc;
 
 use crate::foundation::canvas::Canvas;
 use crate::foundation::node_chain::NodeChain;
+use crate::foundation::ui::frame_stats;
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
 
 #[derive(Debug, Default)]
@@ -23,6 +24,7 @@ impl LayoutNodeDrawDelegate {
 
     pub(crate) fn draw(&mut self, canvas: &mut dyn Canvas) {
         let outer_coordinator = self.node_chain.as_ref().unwrap().borrow().outer_coordinator.clone();
+        frame_stats::record(|stats| stats.nodes_drawn += 1);
         outer_coordinator.borrow().draw(canvas);
     }
 }
@@ -36,4 +36,6 @@ pub fn MultiChildrenMeasurePolicyDelegate(
     delegate: impl FnMut(&dyn MeasureScope, &mut [&mut dyn Measurable], &Constraints) -> MeasureResult + 'static,
 ) -> MultiChildrenMeasurePolicy {
     delegate.wrap_with_rc_refcell()
-}
\ No newline at end of file
+}
+/// measures a node without borrowing its children up front, they are composed by the measure itself
+pub(crate) type SubcomposeNodeMeasurePolicy = Rc<RefCell<dyn FnMut(&dyn MeasureScope, &Constraints) -> MeasureResult>>;
@@ -3,6 +3,7 @@ use crate::foundation::geometry::IntSize;
 
 use crate::foundation::measurable::MultiChildrenMeasurePolicy;
 use crate::foundation::placeable_place_at::PlaceablePlaceAt;
+use crate::foundation::ui::frame_stats::{record_phase, FramePhase};
 
 use super::{constraint::Constraints, layout_node::LayoutNode};
 
@@ -64,7 +65,9 @@ impl MeasureAndLayoutDelegate {
         self.remeasure_only(layout_node.clone());
 
         layout_node.borrow().for_each_child(|child| {
-            self.recurse_remeasure(child.clone());
+            if !child.borrow().deactivated {
+                self.recurse_remeasure(child.clone());
+            }
         });
 
         self.remeasure_only(layout_node);
@@ -88,14 +91,18 @@ impl MeasureAndLayoutDelegate {
     }
 
     pub(crate) fn measure_only(&mut self) {
-        self.perform_measure_and_layout(|this| {
-            this.recurse_remeasure(this.root.clone());
+        record_phase(FramePhase::Measure, || {
+            self.perform_measure_and_layout(|this| {
+                this.recurse_remeasure(this.root.clone());
+            });
         });
     }
 
     pub(crate) fn measure_and_layout(&mut self) {
-        self.perform_measure_and_layout(|this| {
-            this.remeasure_and_relayout_if_need(this.root.clone());
+        record_phase(FramePhase::Layout, || {
+            self.perform_measure_and_layout(|this| {
+                this.remeasure_and_relayout_if_need(this.root.clone());
+            });
         });
     }
 }
@@ -21,6 +21,8 @@ use crate::foundation::placeable::Placeable;
 use crate::foundation::placeable_impl::PlaceableImpl;
 use crate::foundation::placeable_place_at::PlaceablePlaceAt;
 use crate::foundation::remeasurable::{Remeasurable, StatefulRemeasurable};
+use crate::foundation::ui::frame_stats;
+use crate::foundation::ui::frame_stats::{record_phase, FramePhase};
 use crate::foundation::ui::graphics::graphics_layer_modifier::GraphicsLayerScope;
 use crate::foundation::usage_by_parent::UsageByParent;
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
@@ -179,10 +181,14 @@ impl MeasurePassDelegate {
         }
         self.set_layout_state(LayoutState::Measuring);
         self.measure_pending = false;
+        frame_stats::record(|stats| stats.nodes_remeasured += 1);
 
-        let measure_result = self.get_outer_coordinator()
-            .borrow_mut()
-            .measure(constraint);
+        // a node may be measured while its parent is placed, that time counts as measuring too
+        let measure_result = record_phase(FramePhase::Measure, || {
+            self.get_outer_coordinator()
+                .borrow_mut()
+                .measure(constraint)
+        });
 
         if self.get_layout_state() == LayoutState::Measuring {
             self.mark_layout_pending();
@@ -262,6 +268,7 @@ impl MeasurePassDelegate {
             self.on_before_layout_children();
         }
         self.layout_pending = false;
+        frame_stats::record(|stats| stats.nodes_relaid_out += 1);
         let layout_state = self.layout_state.as_ref().unwrap().clone();
         let old_layout_state = *layout_state.borrow_mut();
         *layout_state.borrow_mut() = LayoutState::LayingOut;
@@ -335,4 +342,103 @@ impl SelfReference for MeasurePassDelegate {
     fn get_self(&self) -> Weak<RefCell<Self>> {
         self.weak_self.clone()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::composer::Composer;
+    use crate::foundation::geometry::IntRect;
+    use crate::foundation::measurable::MultiChildrenMeasurePolicyDelegate;
+    use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+    use crate::foundation::measure_scope::{empty_place_action, MeasureScopeLayoutAction};
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+    use crate::foundation::placement_scope::PlacementScope;
+    use crate::foundation::recomposer::Recomposer;
+    use crate::widgets::layout::Layout;
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = RefCell::new(vec![]);
+        static WIDE: MutableState<bool> = mutable_state_of(false);
+    }
+
+    fn log(event: impl Into<String>) {
+        EVENTS.with(|events| events.borrow_mut().push(event.into()));
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+    }
+
+    /// 10 wide for every child, a child is added once `WIDE` is set
+    #[Composable]
+    fn Sized() {
+        let wide = WIDE.with(|wide| wide.get());
+        Layout(Modifier, MultiChildrenMeasurePolicyDelegate(|scope, measurables, _| {
+            scope.layout((measurables.len() * 10 + 10, 10), empty_place_action)
+        }), || {
+            if wide {
+                BoxLayout(Modifier, |_| {});
+            }
+        });
+    }
+
+    #[Composable]
+    fn Parent() {
+        Layout(Modifier, MultiChildrenMeasurePolicyDelegate(|scope, measurables, constraints| {
+            log("measure parent");
+            let placeables = measurables.iter_mut().map(|measurable| measurable.measure(constraints).1).collect::<Vec<_>>();
+            scope.layout((20, 20), move |scope: &dyn PlacementScope| {
+                log("place parent");
+                placeables.iter().for_each(|placeable| scope.place_relative(placeable, 5, 5));
+            })
+        }), || BoxLayout(Modifier, |_| {}));
+    }
+
+    fn content() {
+        // `Parent` follows `Sized` in a row, it moves whenever `Sized` changes its width
+        Layout(Modifier, MultiChildrenMeasurePolicyDelegate(|scope, measurables, constraints| {
+            let measured = measurables.iter_mut().map(|measurable| measurable.measure(constraints)).collect::<Vec<_>>();
+            scope.layout((100, 20), move |scope: &dyn PlacementScope| {
+                measured.iter().fold(0, |x, (size, placeable)| {
+                    scope.place_relative(placeable, x, 0);
+                    x + size.width as i32
+                });
+            })
+        }), || {
+            Sized();
+            Parent();
+        });
+    }
+
+    fn measure_and_layout(owner: &Rc<RefCell<SkiaBaseOwner>>) {
+        Composer::apply_changes();
+        Composer::apply_deferred_changes();
+        MeasureLayoutDeferActionManager::with_manager(|defer_measure, defer_layout| {
+            owner.borrow_mut().dispatch_measure(300, 200);
+            defer_measure();
+            owner.borrow_mut().dispatch_layout();
+            defer_layout();
+        });
+    }
+
+    #[test]
+    fn moving_a_node_without_measuring_it_places_its_children_again() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(content);
+        measure_and_layout(&owner);
+        assert_eq!(take_events(), vec!["measure parent", "place parent"]);
+
+        WIDE.with(|wide| wide.set(true));
+        Recomposer::new().run_until_idle(3);
+        measure_and_layout(&owner);
+        assert_eq!(take_events(), vec!["place parent"]);
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
 
 use auto_delegate::delegate;
 
@@ -7,12 +8,13 @@ use crate::foundation::measure_scope::MeasureScope;
 use crate::foundation::placement_scope::PlacementScope;
 use crate::foundation::placement_scope_impl::PlacementScopeImpl;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct MeasureResult {
     pub(crate) width: usize,
     pub(crate) height: usize,
 
-    placement_block: Option<Box<dyn FnOnce(&dyn PlacementScope)>>,
+    /// kept after placing, a node placed again without being measured again places its children with it
+    placement_block: Option<Rc<dyn Fn(&dyn PlacementScope)>>,
 }
 
 impl Debug for MeasureResult {
@@ -31,7 +33,7 @@ impl PartialEq for MeasureResult {
                 true
             }
             (Some(left_placement_block), Some(right_placement_block)) => {
-                std::ptr::eq(left_placement_block, right_placement_block)
+                Rc::ptr_eq(left_placement_block, right_placement_block)
             }
             _ => {
                 false
@@ -48,7 +50,7 @@ pub trait MeasureResultProvider {
 }
 
 impl MeasureResult {
-    pub(crate) fn new(size: IntSize, placement_block: Option<Box<dyn FnOnce(&dyn PlacementScope)>>) -> Self {
+    pub(crate) fn new(size: IntSize, placement_block: Option<Rc<dyn Fn(&dyn PlacementScope)>>) -> Self {
         MeasureResult {
             width: size.width,
             height: size.height,
@@ -56,14 +58,17 @@ impl MeasureResult {
         }
     }
 
-    pub(crate) fn place_children(&mut self, measure_scope: &dyn MeasureScope) {
-        let place_action = self.placement_block.take();
-        if let Some(place_action) = place_action {
+    pub(crate) fn place_children(&self, measure_scope: &dyn MeasureScope) {
+        if let Some(place_action) = self.placement_block.as_ref() {
             let placement_scope = PlacementScopeImpl::new(self.width, self.height, measure_scope);
             place_action(&placement_scope);
         }
     }
 
+    pub(crate) fn placement_block(&self) -> Option<Rc<dyn Fn(&dyn PlacementScope)>> {
+        self.placement_block.clone()
+    }
+
     pub(crate) fn as_int_size(&self) -> IntSize {
         IntSize::new(self.width, self.height)
     }
@@ -1,9 +1,10 @@
+use std::rc::Rc;
+
 use auto_delegate::delegate;
 
 use crate::foundation::geometry::{Density, IntSize};
 use crate::foundation::measure_result::MeasureResult;
 use crate::foundation::placement_scope::PlacementScope;
-use crate::foundation::utils::box_wrapper::WrapWithBox;
 
 use super::layout_direction::LayoutDirection;
 
@@ -22,10 +23,11 @@ pub struct MeasureScopeImpl {
 }
 
 pub trait MeasureScopeLayoutAction {
+    /// `place_action` runs whenever the node is placed, also when its parent moves it without measuring it again
     fn layout(
         &self,
         size: impl Into<IntSize>,
-        place_action: impl FnOnce(&dyn PlacementScope) + 'static,
+        place_action: impl Fn(&dyn PlacementScope) + 'static,
     ) -> MeasureResult;
 
     fn layout_without_place(
@@ -35,8 +37,8 @@ pub trait MeasureScopeLayoutAction {
 }
 
 impl<T> MeasureScopeLayoutAction for T where T: ?Sized + MeasureScope {
-    fn layout(&self, size: impl Into<IntSize>, place_action: impl FnOnce(&dyn PlacementScope) + 'static) -> MeasureResult {
-        MeasureResult::new(size.into(), Some(place_action.wrap_with_box()))
+    fn layout(&self, size: impl Into<IntSize>, place_action: impl Fn(&dyn PlacementScope) + 'static) -> MeasureResult {
+        MeasureResult::new(size.into(), Some(Rc::new(place_action)))
     }
 
     fn layout_without_place(
@@ -0,0 +1,154 @@
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use crate::foundation::memory::leak_token::with_live_objects;
+
+/// the tags of the objects making up the node tree of a composition
+const NODE_TAGS: [&str; 3] = ["LayoutNode", "NodeCoordinatorImpl", "ModifierNodeImpl"];
+
+/// The objects tagged with `#[Leak]` which are alive on the current thread. Each object is known by
+/// the id of its token, so a report only counts what is new even if as many objects of the same tag
+/// were freed in between. With the `leak-backtrace` feature the report also knows where each of them
+/// was allocated.
+#[derive(Debug, Clone, Default)]
+pub struct LeakReport {
+    live: BTreeMap<u64, &'static str>,
+    backtraces: BTreeMap<u64, Arc<Backtrace>>,
+}
+
+impl LeakReport {
+    /// a snapshot of the objects alive right now
+    pub fn capture() -> Self {
+        with_live_objects(|live_objects| Self {
+            live: live_objects.tags.iter().map(|(&id, &tag)| (id, tag)).collect(),
+            #[cfg(feature = "leak-backtrace")]
+            backtraces: live_objects.backtraces.iter().map(|(&id, backtrace)| (id, backtrace.clone())).collect(),
+            #[cfg(not(feature = "leak-backtrace"))]
+            backtraces: BTreeMap::new(),
+        })
+    }
+
+    pub fn live_count(&self, tag: &str) -> usize {
+        self.live.values().filter(|&&live_tag| live_tag == tag).count()
+    }
+
+    /// the count of live objects of each tag, tags without live objects are left out
+    pub fn live_counts(&self) -> impl Iterator<Item=(&'static str, usize)> + '_ {
+        let mut counts = BTreeMap::new();
+        self.live.values().for_each(|&tag| *counts.entry(tag).or_insert(0) += 1);
+        counts.into_iter()
+    }
+
+    /// where the live objects of `tag` were allocated, empty without the `leak-backtrace` feature
+    pub fn backtraces<'a>(&'a self, tag: &'a str) -> impl Iterator<Item=&'a Backtrace> + 'a {
+        self.backtraces.iter()
+            .filter(move |(id, _)| self.live.get(id).is_some_and(|&live_tag| live_tag == tag))
+            .map(|(_, backtrace)| backtrace.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+
+    /// the objects which are alive now but were not when `earlier` was captured
+    pub fn diff(&self, earlier: &LeakReport) -> LeakReport {
+        self.filter(|id, _| !earlier.live.contains_key(&id))
+    }
+
+    /// the part of this report about `tags`
+    pub fn only(&self, tags: &[&str]) -> LeakReport {
+        self.filter(|_, tag| tags.contains(&tag))
+    }
+
+    fn filter(&self, predicate: impl Fn(u64, &'static str) -> bool) -> LeakReport {
+        let live = self.live.iter()
+            .filter(|(&id, &tag)| predicate(id, tag))
+            .map(|(&id, &tag)| (id, tag))
+            .collect::<BTreeMap<_, _>>();
+        Self {
+            backtraces: self.backtraces.iter()
+                .filter(|(id, _)| live.contains_key(id))
+                .map(|(&id, backtrace)| (id, backtrace.clone()))
+                .collect(),
+            live,
+        }
+    }
+}
+
+impl Display for LeakReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (tag, count) in self.live_counts() {
+            writeln!(f, "{tag}: {count} alive")?;
+            for backtrace in self.backtraces(tag) {
+                writeln!(f, "allocated at:\n{backtrace}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run `block` and panic if any layout node, node coordinator or modifier node created by it is
+/// still alive afterwards, e.g. because a composition created in `block` holds an `Rc` cycle.
+pub fn assert_no_node_leaks<R>(block: impl FnOnce() -> R) -> R {
+    let before = LeakReport::capture();
+    let result = block();
+    let leaked = LeakReport::capture().diff(&before).only(&NODE_TAGS);
+    if !leaked.is_empty() {
+        panic!("nodes survived the block:\n{leaked}");
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::foundation::composer::Composer;
+    use crate::foundation::composition::Composition;
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::memory::leak_report::{assert_no_node_leaks, LeakReport};
+    use crate::foundation::modifier::Modifier;
+    use crate::widgets::r#box::BoxLayout;
+
+    #[test]
+    fn diff_counts_new_objects() {
+        let before = LeakReport::capture();
+        let root = LayoutNode::new();
+        let diff = LeakReport::capture().diff(&before);
+        assert_eq!(diff.live_count("LayoutNode"), 1, "{diff}");
+        assert!(diff.live_count("NodeCoordinatorImpl") >= 1, "{diff}");
+        assert!(diff.live_counts().any(|(tag, count)| tag == "LayoutNode" && count == 1));
+
+        // one node freed and another allocated leave the count unchanged, the new one is still reported
+        let before = LeakReport::capture();
+        drop(root);
+        let replacement = LayoutNode::new();
+        let diff = LeakReport::capture().diff(&before).only(&["LayoutNode"]);
+        assert_eq!(diff.live_count("LayoutNode"), 1, "{diff}");
+        assert_eq!(diff.live_counts().count(), 1);
+
+        drop(replacement);
+        assert!(LeakReport::capture().diff(&before).is_empty());
+    }
+
+    #[test]
+    fn composition_dropped_in_the_block_leaves_no_nodes() {
+        assert_no_node_leaks(|| {
+            let root = LayoutNode::new();
+            let composition = Composition::new(root.clone());
+            composition.set_content(|| {
+                BoxLayout(Modifier, |_| {
+                    BoxLayout(Modifier, |_| {});
+                });
+            });
+            Composer::apply_changes();
+            assert_eq!(root.borrow().children.borrow().len(), 1);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "nodes survived")]
+    fn forgotten_node_is_reported() {
+        assert_no_node_leaks(|| std::mem::forget(LayoutNode::new()));
+    }
+}
@@ -1,16 +1,37 @@
-use std::collections::hash_map::Entry;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::marker::PhantomData;
-use std::sync::Mutex;
+#[cfg(feature = "leak-backtrace")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "leak-backtrace")]
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use lazy_static::lazy_static;
+use crate::foundation::memory::leak_report::LeakReport;
 
 pub(crate) trait LeakableObject {
     fn tag() -> &'static str;
 }
 
-lazy_static! {
-    static ref TAG_REMEMBER: Mutex<HashMap<&'static str, usize>> = Mutex::new(HashMap::new());
+thread_local! {
+    /// the tagged objects alive on this thread, they are `Rc` based so they never leave it
+    static LIVE_OBJECTS: RefCell<LiveObjects> = RefCell::new(LiveObjects::default());
+}
+
+static NEXT_TOKEN_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Default)]
+pub(crate) struct LiveObjects {
+    /// the tag of each live object by the id of its token, so that an object freed and another one
+    /// allocated in its place are told apart
+    pub(crate) tags: HashMap<u64, &'static str>,
+    /// where each live object was allocated by the id of its token, only kept with the `leak-backtrace` feature
+    #[cfg(feature = "leak-backtrace")]
+    pub(crate) backtraces: HashMap<u64, Arc<Backtrace>>,
+}
+
+pub(crate) fn with_live_objects<R>(block: impl FnOnce(&LiveObjects) -> R) -> R {
+    LIVE_OBJECTS.with(|live_objects| block(&live_objects.borrow()))
 }
 
 #[derive(Debug)]
@@ -19,6 +40,7 @@ pub(crate) struct LeakToken<T>
         T: LeakableObject,
 {
     tag: &'static str,
+    id: u64,
     _data: PhantomData<T>,
 }
 
@@ -27,16 +49,17 @@ impl<T> Default for LeakToken<T>
         T: LeakableObject,
 {
     fn default() -> Self {
-        match TAG_REMEMBER.lock().expect("").entry(T::tag()) {
-            Entry::Occupied(mut entry) => {
-                *entry.get_mut() += 1;
-            }
-            Entry::Vacant(mut entry) => {
-                entry.insert(1);
-            }
-        }
+        let id = NEXT_TOKEN_ID.fetch_add(1, Ordering::Relaxed);
+        LIVE_OBJECTS.with(|live_objects| {
+            let mut live_objects = live_objects.borrow_mut();
+            live_objects.tags.insert(id, T::tag());
+            #[cfg(feature = "leak-backtrace")]
+            live_objects.backtraces.insert(id, Arc::new(Backtrace::force_capture()));
+        });
+
         Self {
             tag: T::tag(),
+            id,
             _data: PhantomData::default(),
         }
     }
@@ -47,30 +70,25 @@ impl<T> Drop for LeakToken<T>
         T: LeakableObject,
 {
     fn drop(&mut self) {
-        match TAG_REMEMBER.lock().expect("").entry(T::tag()) {
-            Entry::Occupied(mut entry) => {
-                *entry.get_mut() -= 1;
+        // the thread locals may be gone already when the thread is shutting down
+        let _ = LIVE_OBJECTS.try_with(|live_objects| {
+            let mut live_objects = live_objects.borrow_mut();
+            if live_objects.tags.remove(&self.id).is_none() {
+                panic!("{} dropped without being counted", self.tag);
             }
-            Entry::Vacant(mut entry) => {
-                panic!()
-            }
-        }
+            #[cfg(feature = "leak-backtrace")]
+            live_objects.backtraces.remove(&self.id);
+        });
     }
 }
 
-pub fn validate_leak() {
-    let mut leak_count = 0;
-
-    TAG_REMEMBER
-        .lock()
-        .expect("")
-        .iter()
-        .for_each(|(key, &value)| {
-            if value != 0 {
-                println!("{key} leaks for {value} times");
-                leak_count += 1;
-            }
-        });
+/// log the tagged objects alive on this thread, returns them as a report
+pub fn validate_leak() -> LeakReport {
+    let report = LeakReport::capture();
+    report.live_counts().for_each(|(key, value)| {
+        log::warn!("{key} leaks for {value} times");
+    });
 
-    println!("leak analyze complete, found leak objects: {leak_count}");
+    log::info!("leak analyze complete, found leak objects: {}", report.live_counts().count());
+    report
 }
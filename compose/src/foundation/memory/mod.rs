@@ -1 +1,2 @@
 pub mod leak_token;
+pub mod leak_report;
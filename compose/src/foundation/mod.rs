@@ -1,5 +1,5 @@
 pub mod canvas;
-pub(crate) mod compose_node;
+pub mod compose_node;
 pub mod composer;
 pub(crate) mod composer_impl;
 pub mod constraint;
@@ -54,19 +54,36 @@ pub mod background;
 pub(crate) mod layout_node_draw_delegate;
 pub(crate) mod modifier_node;
 pub mod remember;
+pub mod key;
+pub mod reusable_content;
+pub mod error_boundary;
+pub mod inspector;
+pub mod movable_content;
+pub mod saveable;
+pub mod produce_state;
+pub mod collect_as_state;
+pub mod effects;
+pub mod composition_local;
 pub mod snapshot_value;
-mod remember_observer;
+pub mod mutable_state;
+pub mod snapshot;
+pub mod remember_observer;
 pub mod recompose_scope_impl;
-mod derived_state;
+pub mod recomposer;
+pub mod derived_state;
 pub mod spacer;
+pub mod subcompose_layout;
 mod pending;
 mod remember_manager;
-mod compose_node_lifecycle_callback;
-mod applier;
+pub mod compose_node_lifecycle_callback;
+pub mod applier;
 mod ui_applier;
 pub mod desktop;
-pub(crate) mod composition;
+pub mod composition;
+mod change_list;
 mod application_applier;
-mod updater;
+pub mod updater;
 mod measure_layout_defer_action_manager;
 mod layout_node_hit_test_delegate;
+#[cfg(test)]
+pub(crate) mod test_tree;
@@ -391,12 +391,19 @@ impl Modifier {
 }
 
 impl Modifier {
-    const fn is_element(&self) -> bool {
+    pub(crate) const fn is_element(&self) -> bool {
         match self.inner {
             ModifierInternal::ModifierNodeElement { .. } => true,
             _ => false,
         }
     }
+
+    pub(crate) fn element_name(&self) -> Option<&'static str> {
+        match self.inner {
+            ModifierInternal::ModifierNodeElement { name, .. } => Some(name),
+            _ => None,
+        }
+    }
 }
 
 impl Add for Modifier {
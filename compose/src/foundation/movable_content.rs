@@ -0,0 +1,171 @@
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::foundation::composer::Composer;
+
+/// Composable content which keeps its state when it is invoked at another place of the
+/// composition, see `movable_content_of`.
+#[derive(Clone)]
+pub struct MovableContent {
+    content: Rc<dyn Fn()>,
+}
+
+impl MovableContent {
+    /// compose the content here, if it was composed at another place by the last composition its
+    /// remembered values and layout nodes are moved here instead of being created again
+    pub fn invoke(&self) {
+        Composer::start_movable_content(self.clone());
+        (self.content)();
+        Composer::end_movable_content();
+    }
+
+    pub(crate) fn id(&self) -> *const () {
+        Rc::as_ptr(&self.content) as *const ()
+    }
+}
+
+impl PartialEq for MovableContent {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.content, &other.content)
+    }
+}
+
+impl Debug for MovableContent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MovableContent").field("content", &self.id()).finish()
+    }
+}
+
+/// Wrap `content` so that it can move to another parent without losing its state. The result has
+/// to be remembered, the content is identified by the `MovableContent` instance it is invoked with.
+/// Once it is invoked at another call site by the same recomposition its slots, remembered values
+/// and layout nodes are moved there.
+pub fn movable_content_of(content: impl Fn() + 'static) -> MovableContent {
+    MovableContent { content: Rc::new(content) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use crate::foundation::composition::Composition;
+    use crate::foundation::movable_content::movable_content_of;
+    use crate::foundation::remember::{remember, remember_observer};
+    use crate::foundation::remember_observer::RememberObserver;
+    use crate::foundation::test_tree::{Node, TestNode, test_composition};
+
+    thread_local! {
+        /// the parent the movable content is invoked in, none for any other value
+        static PLACE: Cell<u32> = Cell::new(0);
+        static CREATED: Cell<usize> = Cell::new(0);
+        static EVENTS: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+    }
+
+    struct Observer;
+
+    impl RememberObserver for Observer {
+        fn on_remembered(&self) {
+            EVENTS.with(|events| events.borrow_mut().push("remembered"));
+        }
+
+        fn on_forgotten(&self) {
+            EVENTS.with(|events| events.borrow_mut().push("forgotten"));
+        }
+
+        fn on_abandoned(&self) {
+            EVENTS.with(|events| events.borrow_mut().push("abandoned"));
+        }
+    }
+
+    fn take_events() -> Vec<&'static str> {
+        EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+    }
+
+    fn panel() {
+        remember(&(), || CREATED.with(|created| created.set(created.get() + 1)));
+        remember_observer(&(), || Observer);
+        Node("panel", || Node("label", || {}));
+    }
+
+    fn content() {
+        let panel = remember(&(), || movable_content_of(panel)).borrow().clone();
+        let place = PLACE.with(|place| place.get());
+        Node("first", || {
+            Node("header", || {});
+            if place == 0 {
+                panel.invoke();
+            }
+        });
+        Node("second", || {
+            if place == 1 {
+                panel.invoke();
+            }
+            Node("footer", || {});
+        });
+        if place == 2 {
+            Node("third", || panel.invoke());
+        }
+    }
+
+    fn compose(composition: &Composition, place: u32) {
+        PLACE.with(|current| current.set(place));
+        composition.set_content(content);
+        composition.apply_changes();
+        composition.validate_group();
+    }
+
+    fn child(root: &Rc<RefCell<TestNode>>, path: &[usize]) -> Rc<RefCell<TestNode>> {
+        path.iter().fold(root.clone(), |node, index| node.borrow().children[*index].clone())
+    }
+
+    #[test]
+    fn moved_content_keeps_its_remembered_values_and_nodes() {
+        let (composition, root) = test_composition();
+        compose(&composition, 0);
+        let panel = child(&root, &[0, 1]);
+        assert_eq!(take_events(), vec!["remembered"]);
+
+        compose(&composition, 1);
+        assert_eq!(child(&root, &[0]).borrow().names(), vec!["header"]);
+        assert_eq!(child(&root, &[1]).borrow().names(), vec!["panel", "footer"]);
+        assert!(Rc::ptr_eq(&child(&root, &[1, 0]), &panel));
+        assert_eq!(panel.borrow().names(), vec!["label"]);
+        assert_eq!(CREATED.with(|created| created.get()), 1);
+        assert!(take_events().is_empty());
+    }
+
+    #[test]
+    fn content_moved_twice_in_a_row_is_kept() {
+        let (composition, root) = test_composition();
+        compose(&composition, 0);
+        let panel = child(&root, &[0, 1]);
+
+        compose(&composition, 1);
+        compose(&composition, 2);
+        assert_eq!(root.borrow().names(), vec!["first", "second", "third"]);
+        assert_eq!(child(&root, &[1]).borrow().names(), vec!["footer"]);
+        assert!(Rc::ptr_eq(&child(&root, &[2, 0]), &panel));
+
+        compose(&composition, 0);
+        assert_eq!(root.borrow().names(), vec!["first", "second"]);
+        assert!(Rc::ptr_eq(&child(&root, &[0, 1]), &panel));
+        assert_eq!(CREATED.with(|created| created.get()), 1);
+        assert_eq!(take_events(), vec!["remembered"]);
+    }
+
+    #[test]
+    fn content_no_longer_invoked_is_forgotten() {
+        let (composition, root) = test_composition();
+        compose(&composition, 0);
+        take_events();
+
+        compose(&composition, 3);
+        assert_eq!(take_events(), vec!["forgotten"]);
+        assert_eq!(child(&root, &[0]).borrow().names(), vec!["header"]);
+
+        compose(&composition, 1);
+        assert_eq!(take_events(), vec!["remembered"]);
+        assert_eq!(CREATED.with(|created| created.get()), 2);
+    }
+}
@@ -0,0 +1,181 @@
+use std::cell::{Ref, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::foundation::snapshot::snapshot_impl::{current_snapshot, current_view, is_global, lowest_pinned, notify_read, notify_write, prepare_write, SnapshotId, SnapshotImpl, SnapshotView};
+use crate::foundation::snapshot::state_object::{StateObject, StateObjectId, StateRecordList, StateRecords};
+
+struct StateObjectImpl<T> {
+    id: StateObjectId,
+    records: StateRecordList<T>,
+}
+
+impl<T: 'static> StateObject for StateObjectImpl<T> {
+    fn state_id(&self) -> StateObjectId {
+        self.id
+    }
+
+    fn readable_record_id(&self) -> SnapshotId {
+        self.records.readable_record_id(&current_view()).unwrap_or_default()
+    }
+}
+
+impl<T: 'static> StateRecords for StateObjectImpl<T> {
+    fn readable_record_id_in(&self, view: &SnapshotView) -> Option<SnapshotId> {
+        self.records.readable_record_id(view)
+    }
+
+    fn discard_records(&self, snapshot_id: SnapshotId) {
+        self.records.discard(snapshot_id)
+    }
+}
+
+/// A value holder kept per snapshot. Reads are reported to the snapshot read observers, which is how
+/// the composer learns which scopes to invalidate once a new value is applied.
+pub struct MutableState<T> {
+    state: Rc<StateObjectImpl<T>>,
+}
+
+pub fn mutable_state_of<T: 'static>(value: T) -> MutableState<T> {
+    MutableState {
+        state: Rc::new(StateObjectImpl {
+            id: StateObjectId::next(),
+            records: StateRecordList::new(value),
+        })
+    }
+}
+
+impl<T: 'static> MutableState<T> {
+    pub fn get(&self) -> T where T: Clone {
+        self.borrow().clone()
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        let snapshot = current_snapshot();
+        notify_read(&snapshot, &(self.state.clone() as Rc<dyn StateObject>));
+
+        let view = snapshot.borrow().view.clone();
+        self.state.records.readable(&view)
+    }
+
+    pub fn set(&self, value: T) {
+        let snapshot = current_snapshot();
+        let view = prepare_write(&snapshot, self.state.clone());
+        self.state.records.write(&view, value);
+        self.did_write(&snapshot);
+    }
+
+    pub fn update<R>(&self, block: impl FnOnce(&mut T) -> R) -> R where T: Clone {
+        let snapshot = current_snapshot();
+        let view = prepare_write(&snapshot, self.state.clone());
+        let result = self.state.records.modify(&view, block);
+        self.did_write(&snapshot);
+        result
+    }
+
+    pub fn state_id(&self) -> StateObjectId {
+        self.state.id
+    }
+
+    fn did_write(&self, snapshot: &Rc<RefCell<SnapshotImpl>>) {
+        if is_global(snapshot) {
+            self.state.records.prune(lowest_pinned());
+        }
+        notify_write(snapshot, &(self.state.clone() as Rc<dyn StateObject>));
+    }
+}
+
+impl<T> Clone for MutableState<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone()
+        }
+    }
+}
+
+impl<T> PartialEq for MutableState<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/// a state is saved as the value it holds, and restored as a new state of that value
+impl<T: Serialize + 'static> Serialize for MutableState<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.borrow().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de> + 'static> Deserialize<'de> for MutableState<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(mutable_state_of)
+    }
+}
+
+impl<T: Debug + 'static> Debug for MutableState<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MutableState")
+            .field("value", &self.state.records.readable(&current_view()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+    use crate::foundation::test_tree::test_composition;
+
+    thread_local! {
+        static RUNS: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+        static FIRST: MutableState<u32> = mutable_state_of(0);
+        static SECOND: MutableState<u32> = mutable_state_of(0);
+    }
+
+    fn take_runs() -> Vec<&'static str> {
+        RUNS.with(|runs| std::mem::take(&mut *runs.borrow_mut()))
+    }
+
+    #[Composable]
+    fn ReadsFirst() {
+        RUNS.with(|runs| runs.borrow_mut().push("first"));
+        FIRST.with(|first| first.get());
+    }
+
+    #[Composable]
+    fn ReadsSecond() {
+        RUNS.with(|runs| runs.borrow_mut().push("second"));
+        SECOND.with(|second| second.get());
+    }
+
+    #[Composable]
+    fn Parent() {
+        RUNS.with(|runs| runs.borrow_mut().push("parent"));
+        ReadsFirst();
+        ReadsSecond();
+    }
+
+    #[test]
+    fn write_recomposes_only_the_scopes_which_read_the_state() {
+        let (composition, _root) = test_composition();
+        composition.set_content(Parent);
+        composition.apply_changes();
+        assert_eq!(take_runs(), vec!["parent", "first", "second"]);
+
+        FIRST.with(|first| first.set(1));
+        assert!(composition.recompose());
+        assert_eq!(take_runs(), vec!["first"]);
+
+        SECOND.with(|second| second.set(1));
+        SECOND.with(|second| second.set(2));
+        assert!(composition.recompose());
+        assert_eq!(take_runs(), vec!["second"]);
+
+        assert!(!composition.recompose());
+        assert!(take_runs().is_empty());
+    }
+}
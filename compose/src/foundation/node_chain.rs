@@ -287,38 +287,43 @@ impl NodeChain {
     }
 
     pub(crate) fn update_from(&mut self, mut modifier: Modifier) {
-        // perform expensive reinit for modifier
-        // todo structure update modifier
-
         let mut coordinator_sync_needed = false;
         let padded_head = self.pad_chain();
 
         {
             let mut modifier_container = self.modifier_container.borrow_mut();
-            let mut before = &modifier_container.current;
+            let before = &modifier_container.current;
             let before_size = before.len();
 
-            let mut after = modifier.flatten();
+            let mut after = modifier.flatten().into_iter().filter(|modifier| modifier.is_element()).collect::<Vec<_>>();
             let after_size = after.len();
 
+            // reuse the nodes while the elements keep the same structure, the rest of chain is rebuilt
+            let mut node = padded_head.clone();
             let mut index = 0usize;
+            while index < before_size && index < after_size && before[index].element_name() == after[index].element_name() {
+                let child = node.borrow().get_child().unwrap();
+                node = child;
+                Self::update_node(&after[index], &node);
+                index += 1;
+            }
 
-            if before_size == after_size {
-                todo!()
-            } else if before_size == 0 {
+            if index < before_size {
                 coordinator_sync_needed = true;
+                Self::remove_children(&node, before_size - index);
+            }
 
-                let mut node = padded_head.clone();
+            if index < after_size {
+                coordinator_sync_needed = true;
                 while index < after_size {
                     let parent = node;
                     node = Self::create_and_insert_node_as_child(&mut after[index], parent);
                     index += 1;
                 }
+            }
+
+            if coordinator_sync_needed {
                 self.sync_aggregate_child_kind_set();
-            } else if after_size == 0 {
-                todo!()
-            } else {
-                todo!()
             }
 
             modifier_container.current = after;
@@ -330,6 +335,30 @@ impl NodeChain {
         }
     }
 
+    fn update_node(element: &Modifier, node: &Rc<RefCell<dyn ModifierNode>>) {
+        if let ModifierInternal::ModifierNodeElement { update, .. } = &element.inner {
+            update(node.borrow_mut());
+        }
+    }
+
+    fn remove_children(parent: &Rc<RefCell<dyn ModifierNode>>, count: usize) {
+        for _ in 0..count {
+            let removed = parent.borrow().get_child().unwrap();
+            let next = removed.borrow().get_child();
+
+            if let Some(next) = next.as_ref() {
+                next.borrow_mut().set_parent(Some(Rc::downgrade(parent)));
+            }
+            parent.borrow_mut().set_child(next);
+
+            let mut removed_mut = removed.borrow_mut();
+            removed_mut.set_parent(None);
+            removed_mut.set_child(None);
+            removed_mut.update_coordinator(None);
+            removed_mut.mark_as_detached();
+        }
+    }
+
     fn sync_aggregate_child_kind_set(&self) {
         let mut node = self.get_tail().borrow().get_parent();
         let mut aggregate_child_kind_set = 0;
@@ -286,7 +286,7 @@ impl MeasureResultProvider for NodeCoordinatorImpl {
     }
 
     fn get_measured_result(&mut self) -> Option<MeasureResult> {
-        self.measure_result.take()
+        self.measure_result.clone()
     }
 
     fn has_measure_result(&self) -> bool {
@@ -0,0 +1,21 @@
+use std::future::Future;
+
+use compose_macro::Composable;
+
+use crate as compose;
+use crate::foundation::effects::launch_effect;
+use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+use crate::foundation::remember::remember;
+
+/// A state holding `initial` until `producer` sets other values into it. The future returned by
+/// `producer` is launched on the `ComposeScene` once it enters the composition, it is cancelled when
+/// it leaves the composition, or relaunched with a new future when `keys` changed.
+#[Composable]
+pub fn produce_state<T, K, F>(initial: T, keys: &K, producer: impl FnOnce(MutableState<T>) -> F) -> MutableState<T>
+    where T: 'static, K: PartialEq + Clone + 'static, F: Future<Output=()> + 'static {
+    let state = remember(&(), move || mutable_state_of(initial)).borrow().clone();
+
+    let produced = state.clone();
+    launch_effect(keys, move || producer(produced));
+    state
+}
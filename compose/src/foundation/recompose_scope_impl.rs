@@ -1,28 +1,115 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
 use crate::foundation::composer::ScopeUpdateScope;
+use crate::foundation::slot_table_type::GroupKind;
 
 pub trait RecomposeScope {
     fn invalidate(&self);
 }
 
+/// scopes waiting for recomposition, shared by the composer and every scope it creates
+pub(crate) type Invalidations = Rc<RefCell<Vec<Rc<RefCell<RecomposeScopeImpl>>>>>;
+
 pub(crate) struct RecomposeScopeImpl {
     block: Option<Box<dyn FnMut()>>,
+    invalid: Cell<bool>,
+    /// whether the scope was invalid when its restart group started, such a scope never skips
+    requires_recompose: bool,
+    /// bumped every time the scope is composed, reads recorded with an older epoch are stale
+    epoch: usize,
+    anchor: Weak<RefCell<GroupKind>>,
+    parent: Option<Weak<RefCell<RecomposeScopeImpl>>>,
+    invalidations: Invalidations,
+    weak_self: Weak<RefCell<RecomposeScopeImpl>>,
 }
 
 impl RecomposeScope for RecomposeScopeImpl {
     fn invalidate(&self) {
-        todo!()
+        // a scope which can not restart itself is recomposed by the nearest scope that can,
+        // it is still marked invalid so that it does not skip once the parent calls it again
+        if self.block.is_none() {
+            if let Some(parent) = self.parent.as_ref().and_then(Weak::upgrade) {
+                self.invalid.set(true);
+                parent.borrow().invalidate();
+                return;
+            }
+        }
+
+        if self.invalid.replace(true) {
+            return;
+        }
+
+        if let Some(this) = self.weak_self.upgrade() {
+            self.invalidations.borrow_mut().push(this);
+        }
     }
 }
 
 impl RecomposeScopeImpl {
-    pub(crate) fn new() -> Self {
-        Self {
+    pub(crate) fn new(invalidations: Invalidations,
+                      parent: Option<&Rc<RefCell<RecomposeScopeImpl>>>) -> Rc<RefCell<Self>> {
+        let result = Rc::new(RefCell::new(Self {
             block: None,
-        }
+            invalid: Cell::new(false),
+            requires_recompose: false,
+            epoch: 0,
+            anchor: Weak::new(),
+            parent: parent.map(Rc::downgrade),
+            invalidations,
+            weak_self: Weak::new(),
+        }));
+        result.borrow_mut().weak_self = Rc::downgrade(&result);
+        result
+    }
+
+    /// called every time the restart group owning this scope is composed
+    pub(crate) fn start(&mut self, anchor: Weak<RefCell<GroupKind>>) {
+        self.anchor = anchor;
+        self.requires_recompose = self.invalid.replace(false);
+        self.epoch += 1;
+    }
+
+    /// called instead of composing the body, the reads recorded by the last composition stay current
+    pub(crate) fn skip(&mut self) {
+        self.epoch -= 1;
+    }
+
+    /// the content of the scope left the composition but its slots are kept, it is not recomposed
+    /// until its restart group is composed again
+    pub(crate) fn deactivate(&mut self) {
+        self.anchor = Weak::new();
     }
 
-    pub(crate) fn start(&mut self, token: i32) {
+    pub(crate) fn requires_recompose(&self) -> bool {
+        self.requires_recompose
+    }
+
+    pub(crate) fn is_invalid(&self) -> bool {
+        self.invalid.get()
+    }
+
+    pub(crate) fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// the restart group this scope belongs to, `None` once the group left the composition
+    pub(crate) fn anchor(&self) -> Option<Rc<RefCell<GroupKind>>> {
+        self.anchor.upgrade()
+    }
 
+    pub(crate) fn has_block(&self) -> bool {
+        self.block.is_some()
+    }
+
+    pub(crate) fn take_block(&mut self) -> Option<Box<dyn FnMut()>> {
+        self.block.take()
+    }
+
+    /// put `block` back unless the recomposition registered a new one
+    pub(crate) fn restore_block(&mut self, block: Box<dyn FnMut()>) {
+        if self.block.is_none() {
+            self.block = Some(block);
+        }
     }
 }
 
@@ -30,4 +117,4 @@ impl ScopeUpdateScope for RecomposeScopeImpl {
     fn update_scope(&mut self, block: Box<dyn FnMut()>) {
         self.block = Some(block);
     }
-}
\ No newline at end of file
+}
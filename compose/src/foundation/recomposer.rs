@@ -0,0 +1,151 @@
+use crate::foundation::composer::Composer;
+use crate::foundation::ui::frame_stats::{record_phase, FramePhase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecomposerState {
+    /// no scope is invalid, the composition matches the state it has read
+    Idle,
+    /// some scopes were invalidated and wait for the next frame
+    PendingWork,
+}
+
+/// Drives recomposition frame by frame. Each frame re-runs the invalidated recompose scopes in
+/// composition order and applies the resulting changes, so it can be driven by the window loop
+/// as well as by a test without any window.
+#[derive(Debug, Default)]
+pub struct Recomposer {
+    frame_count: u64,
+}
+
+impl Recomposer {
+    pub fn new() -> Self {
+        Self {
+            frame_count: 0
+        }
+    }
+
+    pub fn state(&self) -> RecomposerState {
+        if Composer::has_invalidations() {
+            RecomposerState::PendingWork
+        } else {
+            RecomposerState::Idle
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.state() == RecomposerState::Idle
+    }
+
+    /// recompose the invalidated scopes and apply the changes they produced,
+    /// returns false if the frame had nothing to do
+    pub fn run_frame(&mut self) -> bool {
+        let recomposed = record_phase(FramePhase::Composition, || {
+            if !Composer::recompose() {
+                return false;
+            }
+
+            Composer::apply_changes();
+            Composer::apply_deferred_changes();
+            true
+        });
+
+        if recomposed {
+            self.frame_count += 1;
+        }
+        recomposed
+    }
+
+    /// run frames until no scope is invalid anymore, gives up after `max_frames` and returns
+    /// whether the recomposer became idle
+    pub fn run_until_idle(&mut self, max_frames: usize) -> bool {
+        for _ in 0..max_frames {
+            if !self.run_frame() {
+                return true;
+            }
+        }
+        self.is_idle()
+    }
+
+    /// number of frames which recomposed something
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::composition::Composition;
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+    use crate::foundation::recomposer::{Recomposer, RecomposerState};
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static COUNT: MutableState<usize> = mutable_state_of(1);
+        static READER_RUNS: Cell<u32> = Cell::new(0);
+        static OTHER_RUNS: Cell<u32> = Cell::new(0);
+    }
+
+    #[Composable]
+    fn Reader() {
+        READER_RUNS.with(|runs| runs.set(runs.get() + 1));
+        for _ in 0..COUNT.with(|count| count.get()) {
+            BoxLayout(Modifier, |_| {});
+        }
+    }
+
+    #[Composable]
+    fn Other() {
+        OTHER_RUNS.with(|runs| runs.set(runs.get() + 1));
+        BoxLayout(Modifier, |_| {});
+    }
+
+    #[test]
+    fn frame_recomposes_only_the_invalidated_composition() {
+        let reader_root = LayoutNode::new();
+        let other_root = LayoutNode::new();
+        let reader = Composition::new(reader_root.clone());
+        let other = Composition::new(other_root.clone());
+        reader.set_content(Reader);
+        other.set_content(Other);
+        reader.apply_changes();
+        other.apply_changes();
+
+        let mut recomposer = Recomposer::new();
+        COUNT.with(|count| count.set(3));
+        assert_eq!(recomposer.state(), RecomposerState::PendingWork);
+        assert!(reader.has_invalidations());
+        assert!(!other.has_invalidations());
+
+        assert!(recomposer.run_frame());
+        assert_eq!(recomposer.frame_count(), 1);
+        assert!(recomposer.is_idle());
+        assert!(!recomposer.run_frame());
+        assert_eq!(READER_RUNS.with(Cell::get), 2);
+        assert_eq!(OTHER_RUNS.with(Cell::get), 1);
+        assert_eq!(reader_root.borrow().children.borrow().len(), 3);
+        assert_eq!(other_root.borrow().children.borrow().len(), 1);
+        reader.validate_group();
+        other.validate_group();
+    }
+
+    #[test]
+    fn goes_idle_when_nothing_is_pending() {
+        let composition = Composition::new(LayoutNode::new());
+        composition.set_content(Other);
+        composition.apply_changes();
+
+        let mut recomposer = Recomposer::new();
+        assert_eq!(recomposer.state(), RecomposerState::Idle);
+        assert!(!recomposer.run_frame());
+        assert!(recomposer.run_until_idle(3));
+        assert_eq!(recomposer.frame_count(), 0);
+        assert_eq!(OTHER_RUNS.with(Cell::get), 1);
+    }
+}
@@ -2,9 +2,100 @@ use std::borrow::Borrow;
 
 use compose_macro::Composable;
 
+use crate as compose;
 use crate::foundation::composer::Composer;
+use crate::foundation::remember_observer::RememberObserver;
 use crate::foundation::snapshot_value::SnapShotValue;
 
-pub fn remember<R, T>(key: &R, calculation: impl FnOnce() -> T) -> SnapShotValue<T> where T: 'static, R: Sized + PartialEq<R> + 'static {
+/// the value returned by `calculation` is kept by the composition until `key` changes or the group of the
+/// call is removed. the value is never told about it, even if it implements `RememberObserver`: `T` is
+/// generic, so whether it is an observer can not be resolved here. use `remember_observer` for those
+#[Composable]
+pub fn remember<R, T>(key: &R, calculation: impl FnOnce() -> T) -> SnapShotValue<T> where T: 'static, R: Sized + PartialEq<R> + Clone + 'static {
     Composer::cache(key, calculation)
-}
\ No newline at end of file
+}
+
+#[Composable]
+pub fn remember2<R1, R2, T>(key1: &R1, key2: &R2, calculation: impl FnOnce() -> T) -> SnapShotValue<T>
+    where T: 'static, R1: Sized + PartialEq<R1> + Clone + 'static, R2: Sized + PartialEq<R2> + Clone + 'static {
+    remember(&(key1.clone(), key2.clone()), calculation)
+}
+
+#[Composable]
+pub fn remember3<R1, R2, R3, T>(key1: &R1, key2: &R2, key3: &R3, calculation: impl FnOnce() -> T) -> SnapShotValue<T>
+    where T: 'static, R1: Sized + PartialEq<R1> + Clone + 'static, R2: Sized + PartialEq<R2> + Clone + 'static,
+          R3: Sized + PartialEq<R3> + Clone + 'static {
+    remember(&(key1.clone(), key2.clone(), key3.clone()), calculation)
+}
+
+/// like `remember`, the value is also told when it enters the composition and when it leaves it,
+/// either because its group is removed or because `key` changed. its slot keeps it as a
+/// `dyn RememberObserver` as well, which is what the composer calls when the slot goes away
+#[Composable]
+pub fn remember_observer<R, T>(key: &R, calculation: impl FnOnce() -> T) -> SnapShotValue<T> where T: RememberObserver, R: Sized + PartialEq<R> + Clone + 'static {
+    let changed = Composer::changed(key);
+    SnapShotValue::new(Composer::remember_observer(changed, calculation))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use crate::foundation::remember::{remember, remember_observer};
+    use crate::foundation::remember_observer::RememberObserver;
+    use crate::foundation::test_tree::test_composition;
+
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = RefCell::new(vec![]);
+        static KEY: Cell<u32> = Cell::new(0);
+        static SHOWN: Cell<bool> = Cell::new(true);
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+    }
+
+    struct Counter(u32);
+
+    impl RememberObserver for Counter {
+        fn on_remembered(&self) {
+            EVENTS.with(|events| events.borrow_mut().push(format!("remembered {}", self.0)));
+        }
+
+        fn on_forgotten(&self) {
+            EVENTS.with(|events| events.borrow_mut().push(format!("forgotten {}", self.0)));
+        }
+
+        fn on_abandoned(&self) {
+            EVENTS.with(|events| events.borrow_mut().push(format!("abandoned {}", self.0)));
+        }
+    }
+
+    fn content() {
+        if SHOWN.with(|shown| shown.get()) {
+            let key = KEY.with(|key| key.get());
+            remember_observer(&key, || Counter(key));
+            remember(&key, || Counter(100 + key));
+        }
+    }
+
+    #[test]
+    fn observers_are_told_on_key_change_and_removal() {
+        let (composition, _root) = test_composition();
+        let compose = |key: u32, shown: bool| {
+            KEY.with(|current| current.set(key));
+            SHOWN.with(|current| current.set(shown));
+            composition.set_content(content);
+            composition.apply_changes();
+            take_events()
+        };
+
+        assert_eq!(compose(1, true), vec!["remembered 1"]);
+        assert_eq!(compose(1, true), Vec::<String>::new());
+        assert_eq!(compose(2, true), vec!["forgotten 1", "remembered 2"]);
+        assert_eq!(compose(2, false), vec!["forgotten 2"]);
+        assert_eq!(compose(3, true), vec!["remembered 3"]);
+        composition.dispose();
+        assert_eq!(take_events(), vec!["forgotten 3"]);
+    }
+}
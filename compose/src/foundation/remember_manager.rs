@@ -39,7 +39,12 @@ impl RememberEventDispatcher {
             forgetting.borrow_mut().on_forgotten();
         }
 
-        while let Some(remembering) = self.remembering.pop() {
+        for reusing in std::mem::take(&mut self.reuse) {
+            reusing.borrow_mut().on_reuse();
+        }
+
+        // forgotten in the reverse order, remembered in the order of the composition
+        for remembering in std::mem::take(&mut self.remembering) {
             remembering.borrow_mut().on_remembered();
         }
 
@@ -49,11 +54,16 @@ impl RememberEventDispatcher {
     }
 
     pub(crate) fn dispatch_side_effects(&mut self) {
-        while let Some(side_effect) = self.side_effects.pop() {
+        for side_effect in std::mem::take(&mut self.side_effects) {
             side_effect();
         }
     }
 
+    /// `instance` was created by a composition which is thrown away, it is never remembered
+    pub(crate) fn abandoning(&mut self, instance: Rc<RefCell<dyn RememberObserver>>) {
+        self.abandon.push(instance);
+    }
+
     pub(crate) fn dispatch_abandoned(&mut self) {
         while let Some(abandon) = self.abandon.pop() {
             abandon.borrow_mut().on_abandoned();
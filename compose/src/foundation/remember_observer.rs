@@ -1,8 +1,12 @@
 use std::any::Any;
 
-pub(crate) trait RememberObserver: Any {
+/// Implemented by remembered values which need to know when they enter and leave the composition.
+pub trait RememberObserver: Any {
+    /// the value was remembered by a composition which was applied
     fn on_remembered(&self);
+    /// the value left the composition after it was remembered
     fn on_forgotten(&self);
+    /// the value was created by a composition which was never applied
     fn on_abandoned(&self);
 }
 
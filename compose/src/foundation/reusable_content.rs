@@ -0,0 +1,103 @@
+use std::rc::Rc;
+
+use crate::foundation::composer::Composer;
+use crate::foundation::composer_impl::REUSE_KEY;
+
+/// Compose `content` in a group identified by `key`. When `key` changes, the content composed for
+/// the previous key is recycled for the new one: its reusable nodes are updated in place instead of
+/// being removed and created again, while its remembered values are forgotten and computed anew.
+pub fn ReusableContent<K>(key: K, content: impl FnOnce()) where K: PartialEq + 'static {
+    Composer::start_reusable_group(REUSE_KEY, Rc::new(key));
+    content();
+    Composer::end_reusable_group();
+}
+
+/// Keep the nodes of `content` around while it is not `active`: they are deactivated and the
+/// remembered values of `content` are forgotten, once it becomes active again the nodes are reused.
+pub fn ReusableContentHost(active: bool, content: impl FnOnce()) {
+    Composer::start_reusable_group(REUSE_KEY, Rc::new(active));
+    let active_changed = Composer::changed(&active);
+    if active {
+        content();
+    } else {
+        Composer::deactivate_to_end_group(active_changed);
+    }
+    Composer::end_reusable_group();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::composer::Composer;
+    use crate::foundation::geometry::{IntRect, IntoDp, IntSize};
+    use crate::foundation::layout::size_modifier::SizeModifier;
+    use crate::foundation::layout_node::LayoutNode;
+    use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::node::Owner;
+    use crate::foundation::placeable::Placeable;
+    use crate::foundation::reusable_content::ReusableContentHost;
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static ACTIVE: Cell<bool> = Cell::new(true);
+    }
+
+    fn content() {
+        BoxLayout(Modifier, |_| {
+            BoxLayout(Modifier, |_| {
+                ReusableContentHost(ACTIVE.with(Cell::get), || {
+                    BoxLayout(Modifier.width(50.dp()).height(20.dp()), |_| {});
+                });
+            });
+        });
+    }
+
+    fn compose_and_measure(owner: &Rc<RefCell<SkiaBaseOwner>>) -> Rc<RefCell<LayoutNode>> {
+        owner.borrow().set_content(content);
+        Composer::apply_changes();
+        Composer::apply_deferred_changes();
+        MeasureLayoutDeferActionManager::with_manager(|defer_measure, defer_layout| {
+            owner.borrow_mut().dispatch_measure(300, 200);
+            defer_measure();
+            owner.borrow_mut().dispatch_layout();
+            defer_layout();
+        });
+
+        let root = owner.borrow().get_root();
+        let outer = root.borrow().children.borrow()[0].clone();
+        let parent = outer.borrow().children.borrow()[0].clone();
+        parent
+    }
+
+    fn size(node: &Rc<RefCell<LayoutNode>>) -> IntSize {
+        node.borrow().get_outer_coordinator().borrow().get_size()
+    }
+
+    #[test]
+    fn deactivated_nodes_are_not_measured_drawn_or_hit() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        let parent = compose_and_measure(&owner);
+        let reusable = parent.borrow().children.borrow()[0].clone();
+        assert_eq!(size(&parent), IntSize::new(50, 20));
+
+        ACTIVE.with(|active| active.set(false));
+        let parent = compose_and_measure(&owner);
+        assert!(Rc::ptr_eq(&parent.borrow().children.borrow()[0], &reusable));
+        assert!(reusable.borrow().deactivated);
+        assert!(reusable.borrow().node_chain.borrow().modifier_container.borrow().current.is_empty());
+        assert_eq!(parent.borrow().z_sort_children().len(), 0);
+        assert_eq!(size(&parent), IntSize::new(0, 0));
+
+        ACTIVE.with(|active| active.set(true));
+        let parent = compose_and_measure(&owner);
+        assert!(Rc::ptr_eq(&parent.borrow().children.borrow()[0], &reusable));
+        assert!(!reusable.borrow().deactivated);
+        assert_eq!(parent.borrow().z_sort_children().len(), 1);
+        assert_eq!(size(&parent), IntSize::new(50, 20));
+        assert_eq!(size(&reusable), IntSize::new(50, 20));
+    }
+}
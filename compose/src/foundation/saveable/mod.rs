@@ -0,0 +1,3 @@
+pub mod saver;
+pub mod saveable_state_registry;
+pub mod remember_saveable;
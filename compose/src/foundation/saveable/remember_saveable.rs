@@ -0,0 +1,147 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::foundation::composer::Composer;
+use crate::foundation::key::caller_key;
+use crate::foundation::remember::{remember, remember_observer};
+use crate::foundation::remember_observer::RememberObserver;
+use crate::foundation::saveable::saveable_state_registry::{SaveableStateRegistry, LOCAL_SAVEABLE_STATE_REGISTRY};
+use crate::foundation::saveable::saver::{AutoSaver, Saver};
+use crate::foundation::snapshot_value::SnapShotValue;
+
+/// registers the value of a `remember_saveable` to the registry while it is in the composition
+struct SaveableHolder {
+    registry: Option<Rc<SaveableStateRegistry>>,
+    key: String,
+    provider: Rc<dyn Fn() -> Option<Value>>,
+    id: Cell<Option<usize>>,
+}
+
+impl RememberObserver for SaveableHolder {
+    fn on_remembered(&self) {
+        if let Some(registry) = self.registry.as_ref() {
+            let provider = self.provider.clone();
+            self.id.set(Some(registry.register_provider(&self.key, move || provider())));
+        }
+    }
+
+    fn on_forgotten(&self) {
+        if let (Some(registry), Some(id)) = (self.registry.as_ref(), self.id.take()) {
+            registry.unregister_provider(&self.key, id);
+        }
+    }
+
+    fn on_abandoned(&self) {}
+}
+
+/// Like `remember`, the value is also saved to the `SaveableStateRegistry` provided by
+/// `LOCAL_SAVEABLE_STATE_REGISTRY`, a composition using the restored registry starts with the
+/// saved value instead of calling `init`.
+#[track_caller]
+pub fn remember_saveable<R, T>(key: &R, init: impl FnOnce() -> T) -> SnapShotValue<T>
+    where T: Serialize + DeserializeOwned + 'static, R: Sized + PartialEq<R> + Clone + 'static {
+    remember_saveable_with(key, AutoSaver, init)
+}
+
+/// `remember_saveable` for values which are saved by `saver`
+#[track_caller]
+pub fn remember_saveable_with<R, T>(key: &R, saver: impl Saver<T> + 'static, init: impl FnOnce() -> T) -> SnapShotValue<T>
+    where T: 'static, R: Sized + PartialEq<R> + Clone + 'static {
+    // each call site has a group of its own, so that its compound hash tells it apart from its siblings
+    let hash = caller_key();
+    Composer::start_group(hash);
+
+    let registry = LOCAL_SAVEABLE_STATE_REGISTRY.current();
+    let state_key = format!("{:x}", Composer::compound_hash());
+
+    let value = remember(key, || {
+        registry.as_ref()
+            .and_then(|registry| registry.consume_restored(&state_key))
+            .and_then(|saved| saver.restore(saved))
+            .unwrap_or_else(init)
+    });
+
+    let saved_value = value.value.clone();
+    remember_observer(key, move || SaveableHolder {
+        registry,
+        key: state_key,
+        provider: Rc::new(move || saver.save(&saved_value.borrow())),
+        id: Cell::new(None),
+    });
+
+    Composer::end_group(hash);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::geometry::IntRect;
+    use crate::foundation::saveable::remember_saveable::{remember_saveable, remember_saveable_with};
+    use crate::foundation::saveable::saveable_state_registry::SaveableStateRegistry;
+    use crate::foundation::saveable::saver::saver;
+
+    thread_local! {
+        static VALUES: RefCell<Vec<Rc<RefCell<u32>>>> = RefCell::new(vec![]);
+        static SAVEABLE: Cell<bool> = Cell::new(true);
+    }
+
+    fn content() {
+        let first = remember_saveable(&(), || 1u32);
+        let second_saver = saver(
+            |value: &u32| SAVEABLE.with(Cell::get).then(|| (*value).into()),
+            |saved| serde_json::from_value(saved).ok(),
+        );
+        let second = remember_saveable_with(&(), second_saver, || 2u32);
+        VALUES.with(|values| *values.borrow_mut() = vec![first.value.clone(), second.value.clone()]);
+    }
+
+    fn values() -> Vec<u32> {
+        VALUES.with(|values| values.borrow().iter().map(|value| *value.borrow()).collect())
+    }
+
+    fn compose(registry: &Rc<SaveableStateRegistry>) -> Rc<RefCell<SkiaBaseOwner>> {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow_mut().set_saveable_state_registry(Some(registry.clone()));
+        owner.borrow().set_content(content);
+        owner.borrow().composition().apply_changes();
+        owner
+    }
+
+    fn restart(registry: &Rc<SaveableStateRegistry>) -> Rc<SaveableStateRegistry> {
+        SaveableStateRegistry::from_json(&registry.to_json()).unwrap()
+    }
+
+    #[test]
+    fn restored_value_replaces_init() {
+        SAVEABLE.with(|saveable| saveable.set(true));
+        let registry = SaveableStateRegistry::new(HashMap::new());
+        let _owner = compose(&registry);
+        assert_eq!(values(), vec![1, 2]);
+
+        VALUES.with(|values| values.borrow().iter().for_each(|value| *value.borrow_mut() += 10));
+        let registry = restart(&registry);
+        let _owner = compose(&registry);
+        assert_eq!(values(), vec![11, 12]);
+    }
+
+    #[test]
+    fn unsaveable_value_is_initialized_again() {
+        SAVEABLE.with(|saveable| saveable.set(false));
+        let registry = SaveableStateRegistry::new(HashMap::new());
+        let _owner = compose(&registry);
+
+        VALUES.with(|values| values.borrow().iter().for_each(|value| *value.borrow_mut() += 10));
+        let registry = restart(&registry);
+        let _owner = compose(&registry);
+        assert_eq!(values(), vec![11, 2]);
+    }
+}
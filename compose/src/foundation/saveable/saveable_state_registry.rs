@@ -0,0 +1,201 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde_json::Value;
+
+use crate::foundation::composition_local::{static_composition_local_of, CompositionLocal};
+
+/// the registry `remember_saveable` restores its values from and saves them to, none by default
+pub static LOCAL_SAVEABLE_STATE_REGISTRY: CompositionLocal<Option<Rc<SaveableStateRegistry>>> = static_composition_local_of(|| None);
+
+type ValueProvider = Rc<dyn Fn() -> Option<Value>>;
+
+/// Keeps the values of `remember_saveable` so that they outlive the composition which remembered
+/// them. Values are keyed by the compound hash of the group they are remembered in, the values of
+/// groups sharing a hash are restored in the order they were saved.
+#[derive(Default)]
+pub struct SaveableStateRegistry {
+    restored: RefCell<HashMap<String, Vec<Value>>>,
+    providers: RefCell<HashMap<String, Vec<(usize, ValueProvider)>>>,
+    next_provider_id: Cell<usize>,
+}
+
+impl SaveableStateRegistry {
+    pub fn new(restored: HashMap<String, Vec<Value>>) -> Rc<Self> {
+        Rc::new(Self {
+            restored: RefCell::new(restored),
+            ..Default::default()
+        })
+    }
+
+    /// a registry restoring the values of `json`, as written by `to_json`
+    pub fn from_json(json: &str) -> serde_json::Result<Rc<Self>> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    /// a registry restoring the values saved to `path`, it is empty if the file does not exist
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Rc<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(Self::from_json(&json)?),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::new(HashMap::new())),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// take the next value restored for `key`
+    pub fn consume_restored(&self, key: &str) -> Option<Value> {
+        let mut restored = self.restored.borrow_mut();
+        let values = restored.get_mut(key)?;
+        let value = (!values.is_empty()).then(|| values.remove(0));
+        if values.is_empty() {
+            restored.remove(key);
+        }
+        value
+    }
+
+    /// `provider` is asked for the value to save for `key` until it is unregistered by the returned id
+    pub fn register_provider(&self, key: &str, provider: impl Fn() -> Option<Value> + 'static) -> usize {
+        let id = self.next_provider_id.get();
+        self.next_provider_id.set(id + 1);
+        self.providers.borrow_mut().entry(key.to_string()).or_default().push((id, Rc::new(provider)));
+        id
+    }
+
+    pub fn unregister_provider(&self, key: &str, id: usize) {
+        let mut providers = self.providers.borrow_mut();
+        if let Some(entries) = providers.get_mut(key) {
+            entries.retain(|(entry_id, _)| *entry_id != id);
+            if entries.is_empty() {
+                providers.remove(key);
+            }
+        }
+    }
+
+    /// the values of every registered provider, along with the restored values nobody consumed. The
+    /// keys with a value its provider can not save are left out.
+    pub fn perform_save(&self) -> HashMap<String, Vec<Value>> {
+        let mut saved = self.restored.borrow().clone();
+
+        // the providers read states, which must not happen while the providers are borrowed
+        let providers = self.providers.borrow().iter()
+            .map(|(key, entries)| (key.clone(), entries.iter().map(|(_, provider)| provider.clone()).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        for (key, providers) in providers {
+            // values sharing a key are restored by position, so a key holding a value which can not
+            // be saved is not saved at all rather than restoring the values after it out of place
+            match providers.iter().map(|provider| provider()).collect::<Option<Vec<_>>>() {
+                Some(values) => {
+                    saved.insert(key, values);
+                }
+                None => {
+                    log::warn!("the values saved for {} are skipped, one of them can not be saved", key);
+                    saved.remove(&key);
+                }
+            }
+        }
+
+        saved
+    }
+
+    pub fn to_json(&self) -> String {
+        Value::from_iter(self.perform_save().into_iter().map(|(key, values)| (key, Value::Array(values)))).to_string()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
+
+impl PartialEq for SaveableStateRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for SaveableStateRegistry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SaveableStateRegistry")
+            .field("restored", &self.restored.borrow().len())
+            .field("providers", &self.providers.borrow().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{json, Value};
+
+    use crate::foundation::saveable::saveable_state_registry::SaveableStateRegistry;
+
+    fn saved(registry: &SaveableStateRegistry) -> HashMap<String, Vec<Value>> {
+        registry.perform_save()
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let registry = SaveableStateRegistry::new(HashMap::new());
+        registry.register_provider("a", || Some(json!(1)));
+        registry.register_provider("a", || Some(json!("second")));
+        registry.register_provider("b", || Some(json!({"x": [1, 2]})));
+
+        let restored = SaveableStateRegistry::from_json(&registry.to_json()).unwrap();
+        assert_eq!(saved(&restored), saved(&registry));
+        assert_eq!(restored.consume_restored("a"), Some(json!(1)));
+        assert_eq!(restored.consume_restored("a"), Some(json!("second")));
+        assert_eq!(restored.consume_restored("a"), None);
+        assert_eq!(restored.consume_restored("b"), Some(json!({"x": [1, 2]})));
+    }
+
+    #[test]
+    fn unconsumed_values_are_saved_again() {
+        let registry = SaveableStateRegistry::new(HashMap::from([("a".to_string(), vec![json!(1)])]));
+        registry.register_provider("b", || Some(json!(2)));
+        assert_eq!(saved(&registry), HashMap::from([
+            ("a".to_string(), vec![json!(1)]),
+            ("b".to_string(), vec![json!(2)]),
+        ]));
+    }
+
+    #[test]
+    fn key_with_unsaveable_value_is_skipped() {
+        let registry = SaveableStateRegistry::new(HashMap::from([("a".to_string(), vec![json!(0)])]));
+        registry.register_provider("a", || Some(json!(1)));
+        registry.register_provider("a", || None);
+        registry.register_provider("b", || Some(json!(2)));
+        assert_eq!(saved(&registry), HashMap::from([("b".to_string(), vec![json!(2)])]));
+    }
+
+    #[test]
+    fn unregistered_provider_is_not_saved() {
+        let registry = SaveableStateRegistry::new(HashMap::new());
+        let first = registry.register_provider("a", || Some(json!(1)));
+        registry.register_provider("a", || Some(json!(2)));
+        registry.unregister_provider("a", first);
+        assert_eq!(saved(&registry), HashMap::from([("a".to_string(), vec![json!(2)])]));
+    }
+
+    #[test]
+    fn save_and_load_file() {
+        let path = std::env::temp_dir().join(format!("saveable_state_registry_{}.json", std::process::id()));
+        let registry = SaveableStateRegistry::new(HashMap::new());
+        registry.register_provider("a", || Some(json!([1, 2, 3])));
+        registry.save(&path).unwrap();
+
+        let loaded = SaveableStateRegistry::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().consume_restored("a"), Some(json!([1, 2, 3])));
+    }
+
+    #[test]
+    fn load_missing_file_is_empty() {
+        let path = std::env::temp_dir().join(format!("saveable_state_registry_missing_{}.json", std::process::id()));
+        let registry = SaveableStateRegistry::load(&path).unwrap();
+        assert!(saved(&registry).is_empty());
+    }
+}
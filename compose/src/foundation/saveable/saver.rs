@@ -0,0 +1,47 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Converts a value of `remember_saveable` to the form it is kept in by a `SaveableStateRegistry`.
+pub trait Saver<T> {
+    /// the saved form of `value`, `None` if it can not be saved
+    fn save(&self, value: &T) -> Option<Value>;
+
+    /// the value saved by `save`, `None` if it can not be restored
+    fn restore(&self, saved: Value) -> Option<T>;
+}
+
+/// saves every value which can be serialized, including `MutableState`s of such values
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AutoSaver;
+
+impl<T: Serialize + DeserializeOwned> Saver<T> for AutoSaver {
+    fn save(&self, value: &T) -> Option<Value> {
+        serde_json::to_value(value).ok()
+    }
+
+    fn restore(&self, saved: Value) -> Option<T> {
+        serde_json::from_value(saved).ok()
+    }
+}
+
+struct SaverDelegate<S, R> {
+    save: S,
+    restore: R,
+}
+
+impl<T, S, R> Saver<T> for SaverDelegate<S, R>
+    where S: Fn(&T) -> Option<Value>, R: Fn(Value) -> Option<T> {
+    fn save(&self, value: &T) -> Option<Value> {
+        (self.save)(value)
+    }
+
+    fn restore(&self, saved: Value) -> Option<T> {
+        (self.restore)(saved)
+    }
+}
+
+/// a `Saver` for values which are not serializable themselves
+pub fn saver<T>(save: impl Fn(&T) -> Option<Value>, restore: impl Fn(Value) -> Option<T>) -> impl Saver<T> {
+    SaverDelegate { save, restore }
+}
@@ -2,15 +2,16 @@ use std::any::Any;
 use std::cell::RefCell;
 use std::cell::Ref;
 use std::ops::Deref;
-use std::rc::Rc;
-use crate::foundation::composer_impl::NODE_KEY;
+use std::rc::{Rc, Weak};
 
-use crate::foundation::layout_node::LayoutNode;
+use crate::foundation::composer_impl::REUSABLE_NODE_KEY;
+use crate::foundation::compose_node::ComposedNode;
+use crate::foundation::remember_observer::RememberObserver;
 use crate::foundation::slot_table_type;
 use crate::foundation::slot_table_type::Slot;
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
 
-use super::slot_table_type::{GroupKind, GroupKindIndex};
+use super::slot_table_type::{GroupKind, GroupKindIndex, ObjectKey};
 
 #[derive(Debug, Default)]
 pub(crate) struct SlotTable {
@@ -25,6 +26,7 @@ pub(crate) struct SlotTable {
     pub(crate) writer: usize,
 }
 
+#[derive(Clone)]
 pub(crate) struct SlotReadWriter {
     empty_count: usize,
     slot: Rc<RefCell<Vec<Slot>>>,
@@ -32,8 +34,8 @@ pub(crate) struct SlotReadWriter {
     pub(crate) slot_index_stack: Vec<usize>,
     pub(crate) slot_stack: Vec<Rc<RefCell<Vec<Slot>>>>,
 
-    pub(crate) current_layout_node: Option<Rc<RefCell<LayoutNode>>>,
-    pub(crate) layout_node_stack: Vec<Rc<RefCell<LayoutNode>>>,
+    pub(crate) current_layout_node: Option<ComposedNode>,
+    pub(crate) layout_node_stack: Vec<ComposedNode>,
 }
 
 impl SlotReadWriter {
@@ -65,35 +67,37 @@ impl SlotReadWriter {
         self.empty_count -= 1;
     }
 
+    /// the slots of the current group
+    pub(crate) fn current_slots(&self) -> Rc<RefCell<Vec<Slot>>> {
+        self.slot.clone()
+    }
+
     pub(crate) fn parent(&self) -> Slot {
         let last_index = *self.slot_index_stack.last().unwrap();
         self.slot_stack.last().unwrap().borrow().get(last_index - 1).cloned().unwrap()
     }
 
-    pub(crate) fn skipping(&self) -> bool {
-        self.slot.borrow().first().map(|group| match group.borrow().deref() {
-            GroupKind::Group { skipping, .. } => {
-                *skipping
-            }
-            _ => {
-                panic!()
-            }
-        }).unwrap_or(false)
-    }
-
     fn insert_slot(&mut self, slot_table_data: impl Into<Slot>) {
-        self.slot.borrow_mut().insert(self.slot_visit_index, slot_table_data.into());
-        self.skip_slot();
+        let slot = slot_table_data.into();
+        if !self.slot_stack.is_empty() {
+            slot.borrow_mut().set_parent(Rc::downgrade(&self.parent()));
+        }
+        self.slot.borrow_mut().insert(self.slot_visit_index, slot);
+        self.slot_visit_index += 1;
     }
 
     fn replace_slot(&mut self, slot_table_type: Slot) -> Slot {
         let slot_table_type = std::mem::replace(&mut self.slot.borrow_mut()[self.slot_visit_index], slot_table_type);
-        self.skip_slot();
+        self.slot_visit_index += 1;
         slot_table_type
     }
 
-    pub(crate) fn start_node(&mut self, key: u64, object_key: Option<Box<dyn Any>>) {
-        self.insert_slot(GroupKind::Node())
+    pub(crate) fn start_node(&mut self, key: u64, object_key: Option<Rc<dyn ObjectKey>>) {
+        if key == REUSABLE_NODE_KEY {
+            self.insert_slot(GroupKind::ReusableNode())
+        } else {
+            self.insert_slot(GroupKind::Node())
+        }
     }
 
     pub(crate) fn group_key(&self, slot: Option<Slot>) -> u64 {
@@ -101,27 +105,20 @@ impl SlotReadWriter {
             return 0
         }
 
-        match slot.unwrap().borrow().deref() {
-            GroupKind::Group {
-                key, ..
-            } => {
-                *key
-            }
-            GroupKind::Node { .. } => {
-                NODE_KEY
-            }
-            _ => {
-                panic!("not key group found")
-            }
+        match slot.unwrap().borrow().key() {
+            Some(key) => key,
+            None => panic!("not key group found")
         }
     }
 
-    pub(crate) fn start_group(&mut self, key: u64, depth: usize, object_key: Option<Box<dyn Any>>) {
+    pub(crate) fn start_group(&mut self, key: u64, depth: usize, object_key: Option<Rc<dyn ObjectKey>>) {
         self.insert_slot(GroupKind::Group {
             key,
+            object_key,
             depth,
-            skipping: false,
+            node_count: 0,
             slot_data: vec![].wrap_with_rc_refcell(),
+            parent: Weak::new(),
         })
     }
 
@@ -129,12 +126,39 @@ impl SlotReadWriter {
         self.slot.borrow().get(self.slot_visit_index).cloned()
     }
 
+    /// find the first group with `key` and `object_key` at or after the current slot of the current group
+    pub(crate) fn find_group(&self, key: u64, object_key: Option<&Rc<dyn ObjectKey>>) -> Option<usize> {
+        self.slot.borrow()[self.slot_visit_index..].iter()
+            .position(|slot| slot.borrow().matches(key, object_key))
+            .map(|offset| self.slot_visit_index + offset)
+    }
+
+    /// count of layout nodes held by the slots in `from..to` of the current group
+    pub(crate) fn node_count_between(&self, from: usize, to: usize) -> usize {
+        self.slot.borrow()[from..to].iter().map(|slot| slot.borrow().node_count()).sum()
+    }
+
+    /// move the slot at `from` to the current position, `from` must not be before the current slot
+    pub(crate) fn move_slot_to_current(&mut self, from: usize) {
+        let mut slots = self.slot.borrow_mut();
+        let slot = slots.remove(from);
+        slots.insert(self.slot_visit_index, slot);
+    }
+
+    /// put `slot` which was taken from another group at the current position, it is read next
+    pub(crate) fn insert_moved_slot(&mut self, slot: Slot) {
+        slot.borrow_mut().set_parent(Rc::downgrade(&self.parent()));
+        self.slot.borrow_mut().insert(self.slot_visit_index, slot);
+    }
+
     pub(crate) fn begin_insert_group(&mut self, hash: u64, depth: usize) {
         let group_kind = GroupKind::Group {
             key: hash,
+            object_key: None,
             depth,
-            skipping: false,
+            node_count: 0,
             slot_data: vec![].wrap_with_rc_refcell(),
+            parent: Weak::new(),
         };
 
         self.insert_slot(group_kind.wrap_with_rc_refcell());
@@ -143,9 +167,11 @@ impl SlotReadWriter {
     pub(crate) fn replace_group(&mut self, hash: u64, depth: usize) -> Slot {
         let group_kind = GroupKind::Group {
             key: hash,
+            object_key: None,
             depth,
-            skipping: false,
+            node_count: 0,
             slot_data: vec![].wrap_with_rc_refcell(),
+            parent: Weak::new(),
         };
 
         self.replace_slot(group_kind.wrap_with_rc_refcell())
@@ -153,21 +179,7 @@ impl SlotReadWriter {
 
     pub(crate) fn enter_group(&mut self) {
         let slot = self.slot.borrow().get(self.slot_visit_index - 1).and_then(|slot| {
-            match slot.borrow().deref() {
-                GroupKind::Group {
-                    slot_data, ..
-                } => {
-                    Some(slot_data.clone())
-                }
-                GroupKind::Node {
-                    slot_data, ..
-                } => {
-                    Some(slot_data.clone())
-                }
-                _ => {
-                    None
-                }
-            }
+            slot.borrow().slot_data()
         });
 
         self.slot_stack.push(self.slot.clone());
@@ -184,24 +196,18 @@ impl SlotReadWriter {
         self.slot_visit_index = self.slot_index_stack.pop().unwrap();
     }
 
-    pub(crate) fn begin_use_layout_node(&mut self, layout_node: Rc<RefCell<LayoutNode>>) {
+    pub(crate) fn begin_use_layout_node(&mut self, layout_node: ComposedNode) {
         if let Some(pre_layout_node) = self.current_layout_node.as_ref() {
             self.layout_node_stack.push(pre_layout_node.clone());
         }
         self.current_layout_node = Some(layout_node.clone());
     }
 
-    pub fn use_layout_node(&mut self) -> Rc<RefCell<LayoutNode>> {
-        let node = match self.current_slot().unwrap().borrow().deref() {
-            GroupKind::Node { node, .. } => node.as_ref().unwrap().clone(),
-            _ => panic!("not a layout node"),
-        };
-        self.skip_slot();
-
-        node
+    pub(crate) fn use_layout_node(&mut self) -> ComposedNode {
+        self.parent().borrow().node()
     }
 
-    pub(crate) fn parent_layout_node(&self) -> Option<Rc<RefCell<LayoutNode>>> {
+    pub(crate) fn parent_layout_node(&self) -> Option<ComposedNode> {
         self.layout_node_stack.last().cloned()
     }
 
@@ -215,19 +221,59 @@ impl SlotReadWriter {
         }
     }
 
+    /// skip the current slot, returns the count of layout nodes it holds
     pub fn skip_slot(&mut self) -> usize {
+        let node_count = self.current_slot().map(|slot| slot.borrow().node_count()).unwrap_or(0);
         self.slot_visit_index += 1;
-        1
+        node_count
     }
 
     pub fn pop_current_slot(&mut self) -> Slot {
         self.slot.borrow_mut().remove(self.slot_visit_index)
     }
 
-    pub(crate) fn update(&mut self, value: Rc<RefCell<dyn Any>>) {
+    /// read the value stored at the current slot, `None` if the current slot does not hold a value
+    pub(crate) fn next_value(&mut self) -> Option<Rc<dyn Any>> {
+        let value = match self.current_slot()?.borrow().deref() {
+            GroupKind::CustomType(value) | GroupKind::Remembered { value, .. } => value.clone(),
+            _ => return None,
+        };
+        self.slot_visit_index += 1;
+        Some(value)
+    }
+
+    /// read the remembered observer stored at the current slot, `None` if the current slot does not hold one
+    pub(crate) fn next_observer(&mut self) -> Option<Rc<dyn Any>> {
+        let value = match self.current_slot()?.borrow().deref() {
+            GroupKind::LifecycleObserver { value, .. } => value.clone(),
+            _ => return None,
+        };
+        self.slot_visit_index += 1;
+        Some(value)
+    }
+
+    /// replace the slot read by the last `next_value` or `next_observer` with `observer`
+    pub(crate) fn replace_previous_observer(&mut self, observer: Rc<RefCell<dyn RememberObserver>>, value: Rc<dyn Any>, type_name: &'static str) -> Slot {
+        let index = self.slot_visit_index - 1;
+        std::mem::replace(&mut self.slot.borrow_mut()[index], GroupKind::LifecycleObserver { observer, value, type_name }.into())
+    }
+
+    /// like `replace_previous_value`, `value` is a remembered value of the type named `type_name`
+    pub(crate) fn replace_previous_remembered(&mut self, value: Rc<dyn Any>, type_name: &'static str) -> Slot {
+        let index = self.slot_visit_index - 1;
+        std::mem::replace(&mut self.slot.borrow_mut()[index], GroupKind::Remembered { value, type_name }.into())
+    }
+
+    pub(crate) fn update(&mut self, value: Rc<dyn Any>) {
         self.insert_slot(GroupKind::CustomType(value));
     }
 
+    /// replace the value read by the last `next_value`, returns the previous value
+    pub(crate) fn replace_previous_value(&mut self, value: Rc<dyn Any>) -> Slot {
+        let index = self.slot_visit_index - 1;
+        std::mem::replace(&mut self.slot.borrow_mut()[index], GroupKind::CustomType(value).into())
+    }
+
     pub(crate) fn is_group_end(&self) -> bool {
         self.slot.borrow().len() - self.slot_visit_index == 0
     }
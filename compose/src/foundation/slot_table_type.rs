@@ -1,11 +1,14 @@
-use std::{any::Any, cell::RefCell, rc::Rc};
+use std::{any::Any, cell::RefCell, rc::{Rc, Weak}};
 use std::fmt::{Debug, Formatter, Write};
 use std::hash::Hash;
+use crate::foundation::composer_impl::{NODE_KEY, REUSABLE_NODE_KEY};
 use crate::foundation::modifier::NodeKind;
+use crate::foundation::recompose_scope_impl::RecomposeScopeImpl;
 use crate::foundation::remember_observer::{RememberObserver, RememberObserverDelegate};
 use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
 
 use super::layout_node::LayoutNode;
+use crate::foundation::compose_node::ComposedNode;
 
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub(crate) enum GroupKindIndex {
@@ -17,37 +20,64 @@ pub(crate) enum GroupKindIndex {
     Custom = 4,
 }
 
+/// identity of a group in addition to its key, two groups only match if their object keys are equal
+pub(crate) trait ObjectKey: Any {
+    fn key_equals(&self, other: &dyn ObjectKey) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: PartialEq + 'static> ObjectKey for T {
+    fn key_equals(&self, other: &dyn ObjectKey) -> bool {
+        other.as_any().downcast_ref::<T>().map(|other| other == self).unwrap_or(false)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 pub(crate) enum GroupKind {
     // Empty,
     Hash(u64),
     Group {
         key: u64,
+        object_key: Option<Rc<dyn ObjectKey>>,
         depth: usize,
-        skipping: bool,
+        node_count: usize,
         slot_data: Rc<RefCell<Vec<Slot>>>,
+        parent: Weak<RefCell<GroupKind>>,
     },
 
-    Node { node: Option<Rc<RefCell<LayoutNode>>>, slot_data: Rc<RefCell<Vec<Slot>>> },
-    LifecycleObserver(Rc<RefCell<dyn RememberObserver>>),
+    /// a reusable node keeps its layout node when the content around it is reused for another key
+    Node { node: Option<ComposedNode>, reusable: bool, slot_data: Rc<RefCell<Vec<Slot>>>, parent: Weak<RefCell<GroupKind>> },
+    /// a remembered value which is notified when it enters and leaves the composition,
+    /// `value` is the same object as `observer`
+    LifecycleObserver { observer: Rc<RefCell<dyn RememberObserver>>, value: Rc<dyn Any>, type_name: &'static str },
+    /// a value remembered by `remember`, read like a `CustomType`
+    Remembered { value: Rc<dyn Any>, type_name: &'static str },
 
-    CustomType(Rc<RefCell<dyn Any>>),
+    CustomType(Rc<dyn Any>),
 }
 
 impl GroupKind {
     pub fn Node() -> GroupKind {
-        GroupKind::Node { node: None, slot_data: vec![].wrap_with_rc_refcell() }
+        GroupKind::Node { node: None, reusable: false, slot_data: vec![].wrap_with_rc_refcell(), parent: Weak::new() }
+    }
+    pub fn ReusableNode() -> GroupKind {
+        GroupKind::Node { node: None, reusable: true, slot_data: vec![].wrap_with_rc_refcell(), parent: Weak::new() }
     }
-    pub fn update_node(&mut self, layout_node: Rc<RefCell<LayoutNode>>) {
+    pub(crate) fn update_node(&mut self, composed_node: ComposedNode) {
         match self {
             GroupKind::Node { node, .. } => {
-                *node = Some(layout_node)
+                *node = Some(composed_node)
             }
             _ => {
                 panic!("update node from wrong group kind")
             }
         }
     }
-    pub fn node(&self) -> Rc<RefCell<LayoutNode>> {
+    pub(crate) fn node(&self) -> ComposedNode {
         match self {
             GroupKind::Node { node, .. } => {
                 node.as_ref().unwrap().clone()
@@ -57,6 +87,77 @@ impl GroupKind {
             }
         }
     }
+
+    pub(crate) fn key(&self) -> Option<u64> {
+        match self {
+            GroupKind::Group { key, .. } => Some(*key),
+            GroupKind::Node { reusable: false, .. } => Some(NODE_KEY),
+            GroupKind::Node { reusable: true, .. } => Some(REUSABLE_NODE_KEY),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn slot_data(&self) -> Option<Rc<RefCell<Vec<Slot>>>> {
+        match self {
+            GroupKind::Group { slot_data, .. } | GroupKind::Node { slot_data, .. } => Some(slot_data.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn object_key(&self) -> Option<Rc<dyn ObjectKey>> {
+        match self {
+            GroupKind::Group { object_key, .. } => object_key.clone(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_object_key(&mut self, key: Rc<dyn ObjectKey>) {
+        if let GroupKind::Group { object_key, .. } = self {
+            *object_key = Some(key);
+        }
+    }
+
+    /// whether this slot is the group started with `key` and `object_key`
+    pub(crate) fn matches(&self, key: u64, object_key: Option<&Rc<dyn ObjectKey>>) -> bool {
+        if self.key() != Some(key) {
+            return false;
+        }
+
+        match (self.object_key(), object_key) {
+            (None, None) => true,
+            (Some(current), Some(expected)) => current.key_equals(expected.as_ref()),
+            _ => false,
+        }
+    }
+
+    /// the group or node enclosing this slot, `None` for top level slots
+    pub(crate) fn parent(&self) -> Option<Slot> {
+        match self {
+            GroupKind::Group { parent, .. } | GroupKind::Node { parent, .. } => parent.upgrade(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set_parent(&mut self, parent_slot: Weak<RefCell<GroupKind>>) {
+        if let GroupKind::Group { parent, .. } | GroupKind::Node { parent, .. } = self {
+            *parent = parent_slot;
+        }
+    }
+
+    /// count of layout nodes this slot contributes to the nearest parent node
+    pub(crate) fn node_count(&self) -> usize {
+        match self {
+            GroupKind::Group { node_count, .. } => *node_count,
+            GroupKind::Node { .. } => 1,
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn set_node_count(&mut self, count: usize) {
+        if let GroupKind::Group { node_count, .. } = self {
+            *node_count = count;
+        }
+    }
 }
 
 impl From<GroupKind> for Rc<RefCell<GroupKind>> {
@@ -66,29 +167,64 @@ impl From<GroupKind> for Rc<RefCell<GroupKind>> {
 }
 
 impl GroupKind {
-    pub(crate) fn visit_layout_node(&self, mut visitor: &mut impl FnMut(&Rc<RefCell<LayoutNode>>)) {
+    /// visit every node held by this slot, a node before the nodes below it
+    pub(crate) fn visit_node(&self, visitor: &mut impl FnMut(&ComposedNode)) {
+        if let GroupKind::Node { node: Some(node), .. } = self {
+            visitor(node);
+        }
+
+        if let GroupKind::Group { slot_data, .. } | GroupKind::Node { slot_data, .. } = self {
+            for slot_table_type in slot_data.borrow().iter() {
+                slot_table_type.borrow().visit_node(visitor);
+            }
+        }
+    }
+
+    /// visit the nodes this slot contributes to the nearest parent node
+    pub(crate) fn visit_top_level_node(&self, visitor: &mut impl FnMut(&ComposedNode)) {
         match self {
+            GroupKind::Node { node: Some(node), .. } => visitor(node),
             GroupKind::Group { slot_data, .. } => {
                 for slot_table_type in slot_data.borrow().iter() {
-                    // slot_table_type.data.visit_layout_node(visitor);
+                    slot_table_type.borrow().visit_top_level_node(visitor);
                 }
             }
-            GroupKind::Node { node, .. } => {
-                // visitor(layout_node);
+            _ => {}
+        }
+    }
+
+    /// take the content of this slot out of the composition while keeping its slots, the remembered
+    /// observers are handed to `forgotten` and replaced by placeholders, the recompose scopes stop
+    /// recomposing until they are composed again
+    pub(crate) fn deactivate(&mut self, forgotten: &mut Vec<Rc<RefCell<dyn RememberObserver>>>) {
+        match self {
+            GroupKind::Group { slot_data, .. } | GroupKind::Node { slot_data, .. } => {
+                for slot_table_type in slot_data.borrow().iter() {
+                    slot_table_type.borrow_mut().deactivate(forgotten);
+                }
+            }
+            GroupKind::LifecycleObserver { observer, .. } => {
+                forgotten.push(observer.clone());
+                *self = GroupKind::CustomType(Rc::new(()));
+            }
+            GroupKind::CustomType(value) => {
+                if let Some(scope) = value.downcast_ref::<RefCell<RecomposeScopeImpl>>() {
+                    scope.borrow_mut().deactivate();
+                }
             }
             _ => {}
         }
     }
 
-    pub fn visit_lifecycle_observer(&self, visitor: &mut impl FnMut(&Rc<RefCell<dyn RememberObserver>>)) {
+    pub(crate) fn visit_lifecycle_observer(&self, visitor: &mut impl FnMut(&Rc<RefCell<dyn RememberObserver>>)) {
         match self {
-            GroupKind::Group { slot_data, .. } => {
+            GroupKind::Group { slot_data, .. } | GroupKind::Node { slot_data, .. } => {
                 for slot_table_type in slot_data.borrow().iter() {
-                    // slot_table_type.data.visit_lifecycle_observer(visitor);
+                    slot_table_type.borrow().visit_lifecycle_observer(visitor);
                 }
             }
-            GroupKind::LifecycleObserver(obj) => {
-                visitor(obj);
+            GroupKind::LifecycleObserver { observer, .. } => {
+                visitor(observer);
             }
             _ => {}
         }
@@ -115,20 +251,23 @@ impl Debug for GroupKind {
             GroupKind::Hash(key) => {
                 f.write_str(&format!("GroupKind::Hash({})", key))
             }
-            GroupKind::Group { key: hash, depth, skipping, slot_data } => {
+            GroupKind::Group { key: hash, depth, node_count, slot_data, .. } => {
                 f.debug_struct("GroupKind::Group")
                     .field("hash", hash)
                     .field("depth", depth)
-                    .field("skipping", skipping)
+                    .field("node_count", node_count)
                     .field("slot_data", slot_data)
                     .finish()
             }
-            GroupKind::Node { node, slot_data } => {
+            GroupKind::Node { node, slot_data, .. } => {
                 match node.as_ref() {
                     Some(node) => {
-                        let identify = node.borrow().identify;
+                        let node = match node.downcast::<LayoutNode>() {
+                            Some(layout_node) => format!("LayoutNode({})", layout_node.borrow().identify),
+                            None => "(Custom Node)".to_string(),
+                        };
                         f.debug_struct("GroupKind::Node")
-                            .field("node", &format!("LayoutNode({identify})"))
+                            .field("node", &node)
                             .field("slot_data", slot_data)
                             .finish()
                     }
@@ -137,16 +276,87 @@ impl Debug for GroupKind {
                     }
                 }
             }
-            GroupKind::LifecycleObserver(observer) => {
+            GroupKind::LifecycleObserver { observer, .. } => {
                 f.debug_struct("GroupKind::LifecycleObserver")
                     .field("observer", &observer.as_ptr())
                     .finish()
             }
             GroupKind::CustomType(obj) => {
                 f.debug_struct("GroupKind::CustomType")
-                    .field("custom_type", &(obj.as_ptr()))
+                    .field("custom_type", &Rc::as_ptr(obj))
                     .finish()
             }
+            GroupKind::Remembered { type_name, .. } => {
+                f.debug_struct("GroupKind::Remembered")
+                    .field("type_name", type_name)
+                    .finish()
+            }
+        }
+    }
+}
+
+/// the slots below a group as they were when the snapshot was taken, edits made to them since are
+/// undone by `restore`
+pub(crate) struct SlotSnapshot {
+    slot_data: Vec<(Rc<RefCell<Vec<Slot>>>, Vec<Slot>)>,
+    slots: Vec<(Slot, GroupKind)>,
+}
+
+impl SlotSnapshot {
+    pub(crate) fn take(slot_data: &Rc<RefCell<Vec<Slot>>>) -> Self {
+        let mut snapshot = Self { slot_data: vec![], slots: vec![] };
+        snapshot.collect(slot_data);
+        snapshot
+    }
+
+    fn collect(&mut self, slot_data: &Rc<RefCell<Vec<Slot>>>) {
+        let slots = slot_data.borrow().clone();
+        for slot in slots.iter() {
+            let copy = slot.borrow().shallow_copy();
+            if let Some(children) = copy.slot_data() {
+                self.collect(&children);
+            }
+            self.slots.push((slot.clone(), copy));
+        }
+        self.slot_data.push((slot_data.clone(), slots));
+    }
+
+    pub(crate) fn restore(self) {
+        for (slot, copy) in self.slots {
+            *slot.borrow_mut() = copy;
+        }
+        for (slot_data, slots) in self.slot_data {
+            *slot_data.borrow_mut() = slots;
+        }
+    }
+}
+
+impl GroupKind {
+    /// a copy sharing the slots below this one
+    fn shallow_copy(&self) -> GroupKind {
+        match self {
+            GroupKind::Hash(key) => GroupKind::Hash(*key),
+            GroupKind::Group { key, object_key, depth, node_count, slot_data, parent } => GroupKind::Group {
+                key: *key,
+                object_key: object_key.clone(),
+                depth: *depth,
+                node_count: *node_count,
+                slot_data: slot_data.clone(),
+                parent: parent.clone(),
+            },
+            GroupKind::Node { node, reusable, slot_data, parent } => GroupKind::Node {
+                node: node.clone(),
+                reusable: *reusable,
+                slot_data: slot_data.clone(),
+                parent: parent.clone(),
+            },
+            GroupKind::LifecycleObserver { observer, value, type_name } => GroupKind::LifecycleObserver {
+                observer: observer.clone(),
+                value: value.clone(),
+                type_name: *type_name,
+            },
+            GroupKind::CustomType(value) => GroupKind::CustomType(value.clone()),
+            GroupKind::Remembered { value, type_name } => GroupKind::Remembered { value: value.clone(), type_name: *type_name },
         }
     }
 }
@@ -157,8 +367,8 @@ impl GroupKind {
             GroupKind::Hash(..) => { GroupKindIndex::Hash }
             GroupKind::Group { .. } => GroupKindIndex::Group,
             GroupKind::Node { .. } => GroupKindIndex::LayoutNode,
-            GroupKind::LifecycleObserver(_) => GroupKindIndex::LifecycleObserver,
-            GroupKind::CustomType(_) => GroupKindIndex::Custom,
+            GroupKind::LifecycleObserver { .. } => GroupKindIndex::LifecycleObserver,
+            GroupKind::CustomType(_) | GroupKind::Remembered { .. } => GroupKindIndex::Custom,
         }
     }
 }
@@ -0,0 +1,4 @@
+pub mod snapshot_impl;
+pub mod state_object;
+pub use snapshot_impl::{ObserverHandle, Snapshot, SnapshotApplyResult, SnapshotError, SnapshotId};
+pub use state_object::{StateObject, StateObjectId};
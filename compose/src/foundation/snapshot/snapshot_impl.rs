@@ -0,0 +1,557 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+use crate::foundation::snapshot::state_object::{StateObject, StateObjectId, StateRecords};
+
+pub type SnapshotId = usize;
+
+pub type ReadObserver = Rc<dyn Fn(&Rc<dyn StateObject>)>;
+pub type WriteObserver = Rc<dyn Fn(&Rc<dyn StateObject>)>;
+pub type ApplyObserver = Rc<dyn Fn(&[Rc<dyn StateObject>])>;
+
+/// the records a snapshot is able to read, records of snapshots not yet applied when it was taken are invalid
+#[derive(Clone)]
+pub(crate) struct SnapshotView {
+    pub(crate) id: SnapshotId,
+    invalid: Rc<BTreeSet<SnapshotId>>,
+}
+
+impl SnapshotView {
+    pub(crate) fn is_valid(&self, snapshot_id: SnapshotId) -> bool {
+        snapshot_id <= self.id && !self.invalid.contains(&snapshot_id)
+    }
+
+    /// records below the pin are never read by this view except the most recent of them
+    fn pin(&self) -> SnapshotId {
+        self.invalid.first().copied().unwrap_or(self.id).min(self.id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnapshotKind {
+    Global,
+    ReadOnly,
+    Mutable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotApplyResult {
+    Success,
+    /// another snapshot applied a different value to a state object this snapshot modified,
+    /// the snapshot is left open and should be disposed
+    Failure,
+}
+
+impl SnapshotApplyResult {
+    pub fn succeeded(&self) -> bool {
+        *self == SnapshotApplyResult::Success
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// a snapshot was taken inside `enter` of another one, nested snapshots are not supported
+    Nested,
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Nested => write!(f, "nested snapshots are not supported, take the snapshot outside of `enter`"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+pub(crate) struct SnapshotImpl {
+    pub(crate) view: SnapshotView,
+    kind: SnapshotKind,
+    modified: Vec<Rc<dyn StateRecords>>,
+    modified_ids: HashSet<StateObjectId>,
+    read_observer: Option<ReadObserver>,
+    write_observer: Option<WriteObserver>,
+    disposed: bool,
+}
+
+impl SnapshotImpl {
+    fn new(view: SnapshotView, kind: SnapshotKind, read_observer: Option<ReadObserver>, write_observer: Option<WriteObserver>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            view,
+            kind,
+            modified: vec![],
+            modified_ids: HashSet::new(),
+            read_observer,
+            write_observer,
+            disposed: false,
+        }))
+    }
+}
+
+struct ObserverList<T> {
+    next_id: usize,
+    observers: Vec<(usize, T)>,
+}
+
+impl<T: Clone> ObserverList<T> {
+    fn add(&mut self, observer: T) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.observers.retain(|(observer_id, _)| *observer_id != id);
+    }
+
+    fn snapshot(&self) -> Vec<T> {
+        self.observers.iter().map(|(_, observer)| observer.clone()).collect()
+    }
+}
+
+impl<T> Default for ObserverList<T> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            observers: vec![],
+        }
+    }
+}
+
+struct SnapshotGlobals {
+    next_id: SnapshotId,
+    /// mutable snapshots that are neither applied nor disposed
+    open: BTreeSet<SnapshotId>,
+    /// pin of every snapshot that is not disposed yet, keyed by snapshot id
+    pinned: BTreeMap<SnapshotId, SnapshotId>,
+    global: Rc<RefCell<SnapshotImpl>>,
+    current: Vec<Rc<RefCell<SnapshotImpl>>>,
+
+    read_observers: ObserverList<ReadObserver>,
+    write_observers: ObserverList<WriteObserver>,
+    apply_observers: ObserverList<ApplyObserver>,
+}
+
+impl SnapshotGlobals {
+    fn new() -> Self {
+        let global_view = SnapshotView { id: 1, invalid: Rc::new(BTreeSet::new()) };
+        Self {
+            next_id: 2,
+            open: BTreeSet::new(),
+            pinned: BTreeMap::new(),
+            global: SnapshotImpl::new(global_view, SnapshotKind::Global, None, None),
+            current: vec![],
+            read_observers: ObserverList::default(),
+            write_observers: ObserverList::default(),
+            apply_observers: ObserverList::default(),
+        }
+    }
+
+    fn next_id(&mut self) -> SnapshotId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn open_view(&mut self) -> SnapshotView {
+        SnapshotView { id: self.next_id(), invalid: Rc::new(self.open.clone()) }
+    }
+
+    /// move the global snapshot to a new id, returns the state objects it modified so far
+    fn advance_global(&mut self) -> Vec<Rc<dyn StateRecords>> {
+        let view = self.open_view();
+        let mut global = self.global.borrow_mut();
+        global.view = view;
+        global.modified_ids.clear();
+        std::mem::take(&mut global.modified)
+    }
+
+    fn lowest_pinned(&self) -> SnapshotId {
+        let global_pin = self.global.borrow().view.pin();
+        self.pinned.values().copied().min().unwrap_or(global_pin).min(global_pin)
+    }
+}
+
+thread_local! {
+    static SNAPSHOT_GLOBALS: RefCell<SnapshotGlobals> = RefCell::new(SnapshotGlobals::new());
+}
+
+/// Dispose the observer it was returned for.
+pub struct ObserverHandle {
+    dispose: Option<Box<dyn FnOnce()>>,
+}
+
+impl ObserverHandle {
+    fn new(dispose: impl FnOnce() + 'static) -> Self {
+        Self {
+            dispose: Some(Box::new(dispose))
+        }
+    }
+
+    pub fn dispose(mut self) {
+        if let Some(dispose) = self.dispose.take() {
+            dispose()
+        }
+    }
+}
+
+/// pops the snapshot pushed by `Snapshot::enter`, even if the block panics
+struct EnteredGuard;
+
+impl Drop for EnteredGuard {
+    fn drop(&mut self) {
+        SNAPSHOT_GLOBALS.with(|globals| {
+            globals.borrow_mut().current.pop();
+        });
+    }
+}
+
+/// An isolated view of every state object, changes made in a mutable snapshot only become
+/// visible to other snapshots once it is applied.
+pub struct Snapshot {
+    snapshot: Rc<RefCell<SnapshotImpl>>,
+}
+
+impl Snapshot {
+    /// take a read only snapshot of the currently applied state, fails if another snapshot is entered
+    pub fn take_snapshot(read_observer: Option<ReadObserver>) -> Result<Snapshot, SnapshotError> {
+        Self::validate_not_nested()?;
+        let snapshot = SNAPSHOT_GLOBALS.with(|globals| {
+            let mut globals = globals.borrow_mut();
+            let view = globals.open_view();
+            globals.pinned.insert(view.id, view.pin());
+            SnapshotImpl::new(view, SnapshotKind::ReadOnly, read_observer, None)
+        });
+        Self::advance_global();
+
+        Ok(Snapshot { snapshot })
+    }
+
+    /// take a snapshot whose writes are isolated until `apply` is called, fails if another snapshot is entered
+    pub fn take_mutable_snapshot(read_observer: Option<ReadObserver>, write_observer: Option<WriteObserver>) -> Result<Snapshot, SnapshotError> {
+        Self::validate_not_nested()?;
+        let snapshot = SNAPSHOT_GLOBALS.with(|globals| {
+            let mut globals = globals.borrow_mut();
+            let view = globals.open_view();
+            globals.open.insert(view.id);
+            globals.pinned.insert(view.id, view.pin());
+            SnapshotImpl::new(view, SnapshotKind::Mutable, read_observer, write_observer)
+        });
+        Self::advance_global();
+
+        Ok(Snapshot { snapshot })
+    }
+
+    fn validate_not_nested() -> Result<(), SnapshotError> {
+        let nested = SNAPSHOT_GLOBALS.with(|globals| !globals.borrow().current.is_empty());
+        if nested {
+            return Err(SnapshotError::Nested);
+        }
+        Ok(())
+    }
+
+    /// apply the changes made in the global snapshot and notify the apply observers
+    pub fn send_apply_notifications() {
+        let has_changes = SNAPSHOT_GLOBALS.with(|globals| !globals.borrow().global.borrow().modified.is_empty());
+        if has_changes {
+            Self::advance_global();
+        }
+    }
+
+    /// the global snapshot moves past every snapshot taken so far, later global writes stay invisible to them
+    fn advance_global() {
+        let (changes, observers) = SNAPSHOT_GLOBALS.with(|globals| {
+            let mut globals = globals.borrow_mut();
+            (globals.advance_global(), globals.apply_observers.snapshot())
+        });
+
+        Self::notify_apply_observers(changes, observers);
+    }
+
+    fn notify_apply_observers(changes: Vec<Rc<dyn StateRecords>>, observers: Vec<ApplyObserver>) {
+        if changes.is_empty() {
+            return;
+        }
+
+        let changes = changes.into_iter().map(|state| state as Rc<dyn StateObject>).collect::<Vec<_>>();
+        observers.iter().for_each(|observer| observer(&changes));
+    }
+
+    /// observe every state object read, in any snapshot
+    pub fn register_global_read_observer(observer: impl Fn(&Rc<dyn StateObject>) + 'static) -> ObserverHandle {
+        let id = SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().read_observers.add(Rc::new(observer)));
+        ObserverHandle::new(move || SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().read_observers.remove(id)))
+    }
+
+    /// observe every state object written, in any snapshot
+    pub fn register_global_write_observer(observer: impl Fn(&Rc<dyn StateObject>) + 'static) -> ObserverHandle {
+        let id = SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().write_observers.add(Rc::new(observer)));
+        ObserverHandle::new(move || SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().write_observers.remove(id)))
+    }
+
+    /// observe the state objects changed by every applied snapshot, including the global one
+    pub fn register_apply_observer(observer: impl Fn(&[Rc<dyn StateObject>]) + 'static) -> ObserverHandle {
+        let id = SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().apply_observers.add(Rc::new(observer)));
+        ObserverHandle::new(move || SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().apply_observers.remove(id)))
+    }
+
+    pub fn id(&self) -> SnapshotId {
+        self.snapshot.borrow().view.id
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.snapshot.borrow().kind == SnapshotKind::ReadOnly
+    }
+
+    pub fn has_pending_changes(&self) -> bool {
+        !self.snapshot.borrow().modified.is_empty()
+    }
+
+    /// run `block` with this snapshot as the current snapshot
+    pub fn enter<R>(&self, block: impl FnOnce() -> R) -> R {
+        if self.snapshot.borrow().disposed {
+            panic!("cannot enter a disposed snapshot")
+        }
+
+        SNAPSHOT_GLOBALS.with(|globals| globals.borrow_mut().current.push(self.snapshot.clone()));
+        let _guard = EnteredGuard;
+        block()
+    }
+
+    /// make the changes of this snapshot visible to the global snapshot, fails without applying anything
+    /// if a state object modified here was changed by another snapshot applied in the meantime
+    pub fn apply(&self) -> SnapshotApplyResult {
+        {
+            let snapshot = self.snapshot.borrow();
+            if snapshot.kind != SnapshotKind::Mutable {
+                panic!("only a mutable snapshot can be applied")
+            }
+            if snapshot.disposed {
+                panic!("cannot apply a disposed snapshot")
+            }
+        }
+
+        // flush pending global changes first so they take part in the conflict check
+        Self::send_apply_notifications();
+
+        let (changes, observers) = SNAPSHOT_GLOBALS.with(|globals| {
+            let mut globals = globals.borrow_mut();
+            let mut snapshot = self.snapshot.borrow_mut();
+            let id = snapshot.view.id;
+
+            // the most recent applied records, this snapshot is still open so its own records are excluded
+            let applied_view = SnapshotView { id: globals.next_id, invalid: Rc::new(globals.open.clone()) };
+
+            let mut base_invalid = (*snapshot.view.invalid).clone();
+            base_invalid.insert(id);
+            let base_view = SnapshotView { id, invalid: Rc::new(base_invalid) };
+
+            let conflict = snapshot.modified.iter().any(|state| {
+                state.readable_record_id_in(&applied_view) != state.readable_record_id_in(&base_view)
+            });
+            if conflict {
+                return None;
+            }
+
+            globals.open.remove(&id);
+            globals.pinned.remove(&id);
+            globals.advance_global();
+            snapshot.disposed = true;
+            snapshot.modified_ids.clear();
+
+            Some((std::mem::take(&mut snapshot.modified), globals.apply_observers.snapshot()))
+        }).unzip();
+
+        match changes {
+            Some(changes) => {
+                Self::notify_apply_observers(changes, observers.unwrap_or_default());
+                SnapshotApplyResult::Success
+            }
+            None => SnapshotApplyResult::Failure,
+        }
+    }
+
+    /// release the snapshot, changes of a mutable snapshot that was not applied are discarded
+    pub fn dispose(&self) {
+        let mut snapshot = self.snapshot.borrow_mut();
+        if snapshot.disposed {
+            return;
+        }
+        snapshot.disposed = true;
+
+        let id = snapshot.view.id;
+        std::mem::take(&mut snapshot.modified).into_iter().for_each(|state| state.discard_records(id));
+        snapshot.modified_ids.clear();
+
+        SNAPSHOT_GLOBALS.with(|globals| {
+            let mut globals = globals.borrow_mut();
+            globals.open.remove(&id);
+            globals.pinned.remove(&id);
+        });
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        self.dispose()
+    }
+}
+
+/// the snapshot reads and writes are currently performed in
+pub(crate) fn current_snapshot() -> Rc<RefCell<SnapshotImpl>> {
+    SNAPSHOT_GLOBALS.with(|globals| {
+        let globals = globals.borrow();
+        globals.current.last().unwrap_or(&globals.global).clone()
+    })
+}
+
+pub(crate) fn current_view() -> SnapshotView {
+    current_snapshot().borrow().view.clone()
+}
+
+/// notify the read observers of the current snapshot and the global ones
+pub(crate) fn notify_read(snapshot: &Rc<RefCell<SnapshotImpl>>, state: &Rc<dyn StateObject>) {
+    let read_observer = snapshot.borrow().read_observer.clone();
+    let observers = SNAPSHOT_GLOBALS.with(|globals| globals.borrow().read_observers.snapshot());
+
+    read_observer.iter().chain(observers.iter()).for_each(|observer| observer(state));
+}
+
+/// validate `snapshot` accepts writes and record `state` as modified by it, returns the view to write into
+pub(crate) fn prepare_write(snapshot: &Rc<RefCell<SnapshotImpl>>, state: Rc<dyn StateRecords>) -> SnapshotView {
+    let mut snapshot = snapshot.borrow_mut();
+    if snapshot.kind == SnapshotKind::ReadOnly {
+        panic!("cannot modify a state object in a read-only snapshot")
+    }
+    if snapshot.disposed {
+        panic!("cannot modify a state object in a disposed snapshot")
+    }
+
+    if snapshot.modified_ids.insert(state.state_id()) {
+        snapshot.modified.push(state);
+    }
+    snapshot.view.clone()
+}
+
+/// notify the write observers of the current snapshot and the global ones
+pub(crate) fn notify_write(snapshot: &Rc<RefCell<SnapshotImpl>>, state: &Rc<dyn StateObject>) {
+    let write_observer = snapshot.borrow().write_observer.clone();
+    let observers = SNAPSHOT_GLOBALS.with(|globals| globals.borrow().write_observers.snapshot());
+
+    write_observer.iter().chain(observers.iter()).for_each(|observer| observer(state));
+}
+
+/// the lowest snapshot id any open snapshot may still read below
+pub(crate) fn lowest_pinned() -> SnapshotId {
+    SNAPSHOT_GLOBALS.with(|globals| globals.borrow().lowest_pinned())
+}
+
+pub(crate) fn is_global(snapshot: &Rc<RefCell<SnapshotImpl>>) -> bool {
+    snapshot.borrow().kind == SnapshotKind::Global
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::foundation::mutable_state::mutable_state_of;
+    use crate::foundation::snapshot::{Snapshot, SnapshotApplyResult, SnapshotError};
+
+    #[test]
+    fn mutable_snapshot_writes_are_isolated_until_applied() {
+        let state = mutable_state_of(0);
+        let snapshot = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        snapshot.enter(|| state.set(1));
+        assert_eq!(snapshot.enter(|| state.get()), 1);
+        assert_eq!(state.get(), 0);
+
+        assert_eq!(snapshot.apply(), SnapshotApplyResult::Success);
+        assert_eq!(state.get(), 1);
+    }
+
+    #[test]
+    fn snapshot_does_not_see_later_writes() {
+        let state = mutable_state_of(0);
+        let read_only = Snapshot::take_snapshot(None).unwrap();
+        let mutable = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        state.set(1);
+        Snapshot::send_apply_notifications();
+
+        assert_eq!(read_only.enter(|| state.get()), 0);
+        assert_eq!(mutable.enter(|| state.get()), 0);
+    }
+
+    #[test]
+    fn disposed_snapshot_discards_its_writes() {
+        let state = mutable_state_of(0);
+        let snapshot = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        snapshot.enter(|| state.set(1));
+        snapshot.dispose();
+        assert_eq!(state.get(), 0);
+    }
+
+    #[test]
+    fn apply_fails_when_another_snapshot_applied_the_same_state() {
+        let state = mutable_state_of(0);
+        let other_state = mutable_state_of(0);
+        let first = Snapshot::take_mutable_snapshot(None, None).unwrap();
+        let second = Snapshot::take_mutable_snapshot(None, None).unwrap();
+        let unrelated = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        first.enter(|| state.set(1));
+        second.enter(|| state.set(2));
+        unrelated.enter(|| other_state.set(3));
+
+        assert_eq!(first.apply(), SnapshotApplyResult::Success);
+        assert_eq!(second.apply(), SnapshotApplyResult::Failure);
+        assert_eq!(unrelated.apply(), SnapshotApplyResult::Success);
+        assert_eq!((state.get(), other_state.get()), (1, 3));
+
+        second.dispose();
+        assert_eq!(state.get(), 1);
+    }
+
+    #[test]
+    fn apply_fails_when_the_global_snapshot_changed_the_same_state() {
+        let state = mutable_state_of(0);
+        let snapshot = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        snapshot.enter(|| state.set(1));
+        state.set(2);
+
+        assert_eq!(snapshot.apply(), SnapshotApplyResult::Failure);
+        assert_eq!(state.get(), 2);
+    }
+
+    #[test]
+    fn nested_snapshots_are_an_error() {
+        let snapshot = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        snapshot.enter(|| {
+            assert_eq!(Snapshot::take_snapshot(None).err(), Some(SnapshotError::Nested));
+            assert_eq!(Snapshot::take_mutable_snapshot(None, None).err(), Some(SnapshotError::Nested));
+        });
+        assert!(Snapshot::take_snapshot(None).is_ok());
+    }
+
+    #[test]
+    fn panic_inside_enter_leaves_the_snapshot() {
+        let state = mutable_state_of(0);
+        let snapshot = Snapshot::take_mutable_snapshot(None, None).unwrap();
+
+        let entered = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            snapshot.enter(|| {
+                state.set(1);
+                panic!("block broke");
+            })
+        }));
+        assert!(entered.is_err());
+
+        state.set(2);
+        assert_eq!(snapshot.enter(|| state.get()), 1);
+        assert!(Snapshot::take_snapshot(None).is_ok());
+    }
+}
@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::cell::{Cell, Ref, RefCell};
+use std::rc::Rc;
+use crate::foundation::snapshot::snapshot_impl::{SnapshotId, SnapshotView};
+
+/// records created together with their state object are readable from every snapshot
+pub(crate) const PREEXISTING_SNAPSHOT_ID: SnapshotId = 0;
+
+thread_local! {
+    static NEXT_STATE_OBJECT_ID: Cell<usize> = Cell::new(0);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateObjectId(usize);
+
+impl StateObjectId {
+    pub(crate) fn next() -> StateObjectId {
+        NEXT_STATE_OBJECT_ID.with(|next_id| {
+            let id = next_id.get();
+            next_id.set(id + 1);
+            StateObjectId(id)
+        })
+    }
+}
+
+/// An object that keeps one value per snapshot, reads and writes are isolated by the current snapshot.
+pub trait StateObject: Any {
+    fn state_id(&self) -> StateObjectId;
+
+    /// id of the record readable from the current snapshot, it changes whenever another value becomes readable
+    fn readable_record_id(&self) -> SnapshotId;
+
+    /// the state objects read to calculate this one, empty unless the state is derived
+    fn dependencies(&self) -> Vec<Rc<dyn StateObject>> {
+        vec![]
+    }
+}
+
+/// record bookkeeping used when a mutable snapshot is applied or disposed
+pub(crate) trait StateRecords: StateObject {
+    fn readable_record_id_in(&self, view: &SnapshotView) -> Option<SnapshotId>;
+
+    fn discard_records(&self, snapshot_id: SnapshotId);
+}
+
+struct StateRecord<T> {
+    snapshot_id: SnapshotId,
+    value: T,
+}
+
+/// the records of a state object, at most one per snapshot that wrote to it
+pub(crate) struct StateRecordList<T> {
+    records: RefCell<Vec<StateRecord<T>>>,
+}
+
+impl<T> StateRecordList<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self {
+            records: RefCell::new(vec![StateRecord { snapshot_id: PREEXISTING_SNAPSHOT_ID, value }])
+        }
+    }
+
+    fn readable_index(&self, view: &SnapshotView) -> Option<usize> {
+        self.records.borrow().iter().enumerate()
+            .filter(|(_, record)| view.is_valid(record.snapshot_id))
+            .max_by_key(|(_, record)| record.snapshot_id)
+            .map(|(index, _)| index)
+    }
+
+    pub(crate) fn readable_record_id(&self, view: &SnapshotView) -> Option<SnapshotId> {
+        self.readable_index(view).map(|index| self.records.borrow()[index].snapshot_id)
+    }
+
+    pub(crate) fn readable(&self, view: &SnapshotView) -> Ref<'_, T> {
+        let index = self.readable_index(view).expect("state object has no record readable from the current snapshot");
+        Ref::map(self.records.borrow(), |records| &records[index].value)
+    }
+
+    /// replace the value written by the snapshot of `view`
+    pub(crate) fn write(&self, view: &SnapshotView, value: T) {
+        let mut records = self.records.borrow_mut();
+        match records.iter_mut().find(|record| record.snapshot_id == view.id) {
+            Some(record) => record.value = value,
+            None => records.push(StateRecord { snapshot_id: view.id, value }),
+        }
+    }
+
+    /// modify the value in place, the readable value is copied first if the snapshot of `view` has not written yet
+    pub(crate) fn modify<R>(&self, view: &SnapshotView, block: impl FnOnce(&mut T) -> R) -> R where T: Clone {
+        if self.records.borrow().iter().all(|record| record.snapshot_id != view.id) {
+            let value = self.readable(view).clone();
+            self.records.borrow_mut().push(StateRecord { snapshot_id: view.id, value });
+        }
+
+        let mut records = self.records.borrow_mut();
+        let record = records.iter_mut().find(|record| record.snapshot_id == view.id).unwrap();
+        block(&mut record.value)
+    }
+
+    pub(crate) fn discard(&self, snapshot_id: SnapshotId) {
+        self.records.borrow_mut().retain(|record| record.snapshot_id != snapshot_id);
+    }
+
+    /// drop records that no open snapshot can read anymore, records older than `pinned` are only kept
+    /// if they are the most recent one among them
+    pub(crate) fn prune(&self, pinned: SnapshotId) {
+        let mut records = self.records.borrow_mut();
+        let Some(newest_pinned) = records.iter()
+            .map(|record| record.snapshot_id)
+            .filter(|snapshot_id| *snapshot_id < pinned)
+            .max() else {
+            return;
+        };
+
+        records.retain(|record| record.snapshot_id >= newest_pinned);
+    }
+}
@@ -0,0 +1,323 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use compose_macro::Composable;
+
+use crate as compose;
+use crate::foundation::compose_node::ReusableComposeNode;
+use crate::foundation::composer::Composer;
+use crate::foundation::composition::Composition;
+use crate::foundation::composition_local::CompositionLocalMap;
+use crate::foundation::constraint::Constraints;
+use crate::foundation::geometry::Density;
+use crate::foundation::layout_direction::LayoutDirection;
+use crate::foundation::layout_node::LayoutNode;
+use crate::foundation::measurable::Measurable;
+use crate::foundation::measure_result::MeasureResult;
+use crate::foundation::measure_scope::MeasureScope;
+use crate::foundation::modifier::Modifier;
+use crate::foundation::remember::remember;
+use crate::foundation::slot_table_type::ObjectKey;
+use crate::foundation::ui::align::Alignment;
+use crate::foundation::ui::platform::composition_locals::{LOCAL_DENSITY, LOCAL_LAYOUT_DIRECTION, LOCAL_VIEW_CONFIGURATION};
+use crate::foundation::utils::rc_wrapper::WrapWithRcRefCell;
+use crate::foundation::utils::self_reference::SelfReference;
+use crate::widgets::r#box::remember_box_measure_policy;
+
+pub type SubcomposeMeasurePolicy = Rc<RefCell<dyn FnMut(&mut SubcomposeMeasureScope, &Constraints) -> MeasureResult>>;
+
+#[inline]
+pub fn SubcomposeMeasurePolicyDelegate(
+    delegate: impl FnMut(&mut SubcomposeMeasureScope, &Constraints) -> MeasureResult + 'static,
+) -> SubcomposeMeasurePolicy {
+    delegate.wrap_with_rc_refcell()
+}
+
+/// The measure scope of a `SubcomposeLayout`, its content is composed on demand by `subcompose`.
+pub struct SubcomposeMeasureScope<'a> {
+    measure_scope: &'a dyn MeasureScope,
+    state: Rc<RefCell<SubcomposeLayoutState>>,
+    node: Rc<RefCell<LayoutNode>>,
+    used: usize,
+    /// whether the existing slots compose their content again, they recompose on their own otherwise
+    recompose: bool,
+}
+
+impl MeasureScope for SubcomposeMeasureScope<'_> {
+    fn get_density(&self) -> Density {
+        self.measure_scope.get_density()
+    }
+
+    fn get_layout_direction(&self) -> LayoutDirection {
+        self.measure_scope.get_layout_direction()
+    }
+}
+
+impl SubcomposeMeasureScope<'_> {
+    /// compose `content` into the slot identified by `slot_id` and return the slot as a measurable,
+    /// the content is laid out like the content of a `BoxLayout`. The slots which are not subcomposed
+    /// by a measure pass anymore are disposed once it is done. An existing slot takes `content` only
+    /// when the `SubcomposeLayout` was composed again or is measured with other constraints, the slot
+    /// recomposes the scopes its content invalidated by itself otherwise
+    pub fn subcompose<K: PartialEq + 'static>(&mut self, slot_id: K, content: impl Fn() + 'static) -> Rc<RefCell<dyn Measurable>> {
+        let density = self.get_density();
+        let layout_direction = self.get_layout_direction();
+        let (slot_node, composition, created) = self.state.borrow_mut()
+            .take_slot(&self.node, self.used, Rc::new(slot_id), density, layout_direction);
+        self.used += 1;
+
+        // the slot content runs outside of the state borrow, it may subcompose layouts of its own
+        if created || self.recompose {
+            composition.set_content(content);
+            composition.apply_changes();
+            composition.apply_deferred_changes();
+        }
+
+        let measure_pass_delegate = slot_node.borrow().get_measure_pass_delegate();
+        measure_pass_delegate
+    }
+}
+
+struct SubcomposeSlot {
+    slot_id: Rc<dyn ObjectKey>,
+    node: Rc<RefCell<LayoutNode>>,
+    composition: Rc<Composition>,
+}
+
+/// the slots of a `SubcomposeLayout`, each one is a child node hosting a composition of its own
+#[derive(Default)]
+pub(crate) struct SubcomposeLayoutState {
+    node: Weak<RefCell<LayoutNode>>,
+    measure_policy: Option<SubcomposeMeasurePolicy>,
+    parent_locals: Option<Rc<CompositionLocalMap>>,
+    slots: Vec<SubcomposeSlot>,
+    /// the measure policy changed since the last measure, so may have the content it subcomposes
+    policy_changed: bool,
+    last_constraints: Option<Constraints>,
+}
+
+impl SubcomposeLayoutState {
+    fn attach(this: &Rc<RefCell<Self>>, node: &mut LayoutNode) {
+        this.borrow_mut().node = node.get_self();
+
+        let state = this.clone();
+        node.set_subcompose_measure_policy((move |measure_scope: &dyn MeasureScope, constraints: &Constraints| {
+            Self::measure(&state, measure_scope, constraints)
+        }).wrap_with_rc_refcell());
+    }
+
+    fn update(&mut self, measure_policy: SubcomposeMeasurePolicy, parent_locals: Option<Rc<CompositionLocalMap>>) {
+        self.measure_policy = Some(measure_policy);
+        self.parent_locals = parent_locals;
+        self.policy_changed = true;
+
+        if let Some(node) = self.node.upgrade() {
+            node.borrow().request_remeasure();
+        }
+    }
+
+    fn measure(this: &Rc<RefCell<Self>>, measure_scope: &dyn MeasureScope, constraints: &Constraints) -> MeasureResult {
+        let (node, measure_policy, recompose) = {
+            let mut state = this.borrow_mut();
+            let measure_policy = state.measure_policy.clone().expect("subcompose layout is measured before it is composed");
+            let recompose = state.policy_changed || state.last_constraints != Some(*constraints);
+            state.policy_changed = false;
+            state.last_constraints = Some(*constraints);
+            (state.node.upgrade().unwrap(), measure_policy, recompose)
+        };
+
+        // the slots are inserted while measuring, which must not request another measure of this node
+        node.borrow().ignore_remeasure_requests.set(true);
+
+        let mut scope = SubcomposeMeasureScope {
+            measure_scope,
+            state: this.clone(),
+            node: node.clone(),
+            used: 0,
+            recompose,
+        };
+        let measure_result = {
+            let mut measure_policy = measure_policy.borrow_mut();
+            measure_policy(&mut scope, constraints)
+        };
+
+        this.borrow_mut().dispose_slots_from(&node, scope.used);
+        node.borrow().ignore_remeasure_requests.set(false);
+
+        measure_result
+    }
+
+    /// move the slot of `slot_id` to `index`, creating it if it does not exist yet, returns whether it was created
+    fn take_slot(&mut self,
+                 node: &Rc<RefCell<LayoutNode>>,
+                 index: usize,
+                 slot_id: Rc<dyn ObjectKey>,
+                 density: Density,
+                 layout_direction: LayoutDirection) -> (Rc<RefCell<LayoutNode>>, Rc<Composition>, bool) {
+        let position = self.slots[index..].iter()
+            .position(|slot| slot.slot_id.key_equals(slot_id.as_ref()))
+            .map(|offset| index + offset);
+
+        let created = position.is_none();
+        match position {
+            Some(position) => {
+                if position != index {
+                    let slot = self.slots.remove(position);
+                    self.slots.insert(index, slot);
+                    node.borrow_mut().move_at(position, index, 1);
+                }
+            }
+            None => {
+                let slot_node = LayoutNode::new();
+                {
+                    let mut slot_node = slot_node.borrow_mut();
+                    slot_node.set_density(density);
+                    slot_node.set_layout_direction(layout_direction);
+                    slot_node.set_measure_policy(remember_box_measure_policy(Alignment::TOP_START, false));
+                }
+
+                let composition = Composition::new(slot_node.clone());
+                composition.set_parent_locals(self.parent_locals.clone());
+
+                node.borrow_mut().insert_at(index, slot_node.clone());
+                self.slots.insert(index, SubcomposeSlot {
+                    slot_id,
+                    node: slot_node,
+                    composition: Rc::new(composition),
+                });
+            }
+        }
+
+        let slot = &self.slots[index];
+        (slot.node.clone(), slot.composition.clone(), created)
+    }
+
+    fn dispose_slots_from(&mut self, node: &Rc<RefCell<LayoutNode>>, index: usize) {
+        if index >= self.slots.len() {
+            return;
+        }
+
+        node.borrow_mut().remove_at(index, self.slots.len() - index);
+        self.slots.truncate(index);
+    }
+}
+
+/// A layout which composes its content while it is measured, `measure_policy` calls
+/// `SubcomposeMeasureScope::subcompose` with the incoming constraints at hand.
+#[Composable]
+pub fn SubcomposeLayout(modifier: Modifier, measure_policy: SubcomposeMeasurePolicy) {
+    let materialzed = modifier.materialize();
+    let density = LOCAL_DENSITY.current();
+    let layout_direction = LOCAL_LAYOUT_DIRECTION.current();
+    let view_configuration = LOCAL_VIEW_CONFIGURATION.current();
+    let parent_locals = Composer::current_composition_locals();
+    let state = remember(&(), SubcomposeLayoutState::default).value;
+    // set while composing, the node may be measured as soon as it is inserted
+    state.borrow_mut().update(measure_policy, parent_locals);
+
+    let attached_state = state.clone();
+    ReusableComposeNode(
+        LayoutNode::new,
+        move |updater| {
+            updater.update(density, |node, density| {
+                node.set_density(density);
+            });
+            updater.update(layout_direction, |node, layout_direction| {
+                node.set_layout_direction(layout_direction);
+            });
+            updater.update(view_configuration, |node, view_configuration| {
+                node.set_view_configuration(view_configuration);
+            });
+            updater.set(attached_state, |node, state| {
+                SubcomposeLayoutState::attach(&state, node);
+            });
+            updater.set(materialzed, |node, modifier| {
+                node.set_modifier(modifier);
+            })
+        },
+        || {},
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use compose_macro::Composable;
+
+    use crate as compose;
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::composer::Composer;
+    use crate::foundation::geometry::IntRect;
+    use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+    use crate::foundation::measure_scope::MeasureScopeLayoutAction;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::mutable_state::{mutable_state_of, MutableState};
+    use crate::foundation::placement_scope::PlacementScope;
+    use crate::foundation::recomposer::Recomposer;
+    use crate::foundation::subcompose_layout::{SubcomposeLayout, SubcomposeMeasurePolicyDelegate};
+    use crate::widgets::r#box::BoxLayout;
+
+    thread_local! {
+        static MEASURED: Cell<usize> = Cell::new(0);
+        static CONTENT_SET: Cell<usize> = Cell::new(0);
+        static SLOT_COMPOSED: Cell<usize> = Cell::new(0);
+        static EXTRA: MutableState<bool> = mutable_state_of(false);
+    }
+
+    fn count(counter: &'static std::thread::LocalKey<Cell<usize>>) -> usize {
+        counter.with(|counter| counter.get())
+    }
+
+    fn increment(counter: &'static std::thread::LocalKey<Cell<usize>>) {
+        counter.with(|counter| counter.set(counter.get() + 1));
+    }
+
+    #[Composable]
+    fn SlotContent() {
+        increment(&SLOT_COMPOSED);
+        BoxLayout(Modifier, |_| {});
+        if EXTRA.with(|extra| extra.get()) {
+            BoxLayout(Modifier, |_| {});
+        }
+    }
+
+    fn measure(owner: &Rc<RefCell<SkiaBaseOwner>>, width: usize, height: usize) {
+        Composer::apply_changes();
+        Composer::apply_deferred_changes();
+        MeasureLayoutDeferActionManager::with_manager(|defer_measure, defer_layout| {
+            owner.borrow_mut().dispatch_measure(width, height);
+            defer_measure();
+            owner.borrow_mut().dispatch_layout();
+            defer_layout();
+        });
+    }
+
+    #[test]
+    fn slots_take_their_content_only_when_it_or_the_constraints_changed() {
+        let owner = SkiaBaseOwner::new(IntRect::ZERO);
+        owner.borrow().set_content(|| {
+            SubcomposeLayout(Modifier, SubcomposeMeasurePolicyDelegate(|scope, constraints| {
+                increment(&MEASURED);
+                let measurable = scope.subcompose((), || {
+                    increment(&CONTENT_SET);
+                    SlotContent();
+                });
+                let (size, placeable) = measurable.borrow_mut().measure(constraints);
+                scope.layout(size, move |scope: &dyn PlacementScope| scope.place_relative(&placeable, 0, 0))
+            }));
+        });
+        measure(&owner, 300, 200);
+        assert_eq!((count(&MEASURED), count(&CONTENT_SET), count(&SLOT_COMPOSED)), (1, 1, 1));
+
+        // the slot recomposes by itself
+        EXTRA.with(|extra| extra.set(true));
+        Recomposer::new().run_until_idle(3);
+        measure(&owner, 300, 200);
+        assert_eq!((count(&MEASURED), count(&CONTENT_SET), count(&SLOT_COMPOSED)), (2, 1, 2));
+
+        measure(&owner, 200, 100);
+        assert_eq!((count(&MEASURED), count(&CONTENT_SET)), (3, 2));
+    }
+}
@@ -0,0 +1,98 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::foundation::applier::{AbstractApplier, Applier};
+use crate::foundation::compose_node::ComposeNode;
+use crate::foundation::compose_node_lifecycle_callback::ComposeNodeLifecycleCallback;
+use crate::foundation::composition::Composition;
+
+/// a node of an in-memory tree, the composer tests compose into it instead of layout nodes
+#[derive(Debug, Default)]
+pub(crate) struct TestNode {
+    pub(crate) name: String,
+    pub(crate) children: Vec<Rc<RefCell<TestNode>>>,
+    pub(crate) released: bool,
+}
+
+impl TestNode {
+    pub(crate) fn new(name: impl Into<String>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            name: name.into(),
+            ..Default::default()
+        }))
+    }
+
+    /// the names of the children
+    pub(crate) fn names(&self) -> Vec<String> {
+        self.children.iter().map(|child| child.borrow().name.clone()).collect()
+    }
+}
+
+impl ComposeNodeLifecycleCallback for TestNode {
+    fn on_release(&mut self) {
+        self.released = true;
+    }
+}
+
+pub(crate) struct TestApplier {
+    applier: AbstractApplier<Rc<RefCell<TestNode>>>,
+}
+
+impl TestApplier {
+    pub(crate) fn new(root: Rc<RefCell<TestNode>>) -> Self {
+        Self {
+            applier: AbstractApplier::new(root),
+        }
+    }
+}
+
+impl Applier<Rc<RefCell<TestNode>>> for TestApplier {
+    fn get_current(&self) -> &Rc<RefCell<TestNode>> {
+        self.applier.get_current()
+    }
+
+    fn down(&mut self, node: Rc<RefCell<TestNode>>) {
+        self.applier.down(node)
+    }
+
+    fn up(&mut self) {
+        self.applier.up()
+    }
+
+    fn clear(&mut self) {
+        self.applier.clear()
+    }
+
+    fn insert_top_down(&self, _index: usize, _instance: Rc<RefCell<TestNode>>) {}
+
+    fn insert_bottom_up(&self, index: usize, instance: Rc<RefCell<TestNode>>) {
+        self.get_current().borrow_mut().children.insert(index, instance);
+    }
+
+    fn remove(&self, index: usize, count: usize) {
+        self.get_current().borrow_mut().children.drain(index..index + count);
+    }
+
+    fn r#move(&self, from: usize, to: usize, count: usize) {
+        let mut current = self.get_current().borrow_mut();
+        let moved = current.children.drain(from..from + count).collect::<Vec<_>>();
+        let to = if from < to { to - count } else { to };
+        current.children.splice(to..to, moved);
+    }
+}
+
+/// a composition into a new test tree, along with the root of the tree
+pub(crate) fn test_composition() -> (Composition, Rc<RefCell<TestNode>>) {
+    let root = TestNode::new("root");
+    (Composition::with_applier(root.clone(), TestApplier::new(root.clone())), root)
+}
+
+/// emit a test node named `name` whose children are emitted by `content`
+pub(crate) fn Node(name: impl Into<String>, content: impl FnMut()) {
+    let name = name.into();
+    ComposeNode(
+        || TestNode::new(""),
+        move |updater| updater.update(name, |node: &mut TestNode, name| node.name = name),
+        content,
+    );
+}
@@ -1,6 +1,9 @@
 use crate::foundation::ui::input::internal_pointer_input::PointerInputEventData;
 use std::marker::PhantomData;
 use tokio::runtime::Runtime;
+use tokio::task::{JoinHandle, LocalSet};
+use std::future::Future;
+use crate::foundation::composition_local::{static_composition_local_of, CompositionLocal};
 use crate::foundation::geometry::{Density, Offset};
 use std::any::Any;
 use std::cell::RefCell;
@@ -11,9 +14,35 @@ use crate::foundation::ui::input::pointer_event::{PointerEventType, PointerId, P
 use crate::foundation::ui::input::pointer_event_type::PointerInputEvent;
 use std::rc::Rc;
 use crate::foundation::utils::option_extension::OptionThen;
+use crate::foundation::ui::ui_dispatcher::{DispatchedTasks, UiDispatcher};
+use crate::foundation::ui::frame_stats::{self, FrameRecorder, FrameStats};
+use std::path::Path;
+use std::time::Duration;
+
+/// the tasks of the scene an owner is attached to, the effects composed into the owner are launched onto them
+#[derive(Clone)]
+pub(crate) struct CoroutineTasks(Rc<LocalSet>);
+
+impl CoroutineTasks {
+    pub(crate) fn launch(&self, future: impl Future<Output=()> + 'static) -> JoinHandle<()> {
+        self.0.spawn_local(future)
+    }
+}
+
+impl PartialEq for CoroutineTasks {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+pub(crate) static LOCAL_COROUTINE_TASKS: CompositionLocal<Option<CoroutineTasks>> = static_composition_local_of(|| None);
 
 pub struct ComposeScene {
     coroutine_scope: Runtime,
+    coroutine_tasks: Rc<LocalSet>,
+    dispatched_tasks: DispatchedTasks,
+    frame_recorder: Rc<RefCell<FrameRecorder>>,
+    frame_stats: FrameStats,
     density: Density,
     invalidate: Box<dyn FnMut() + 'static>,
 
@@ -74,6 +103,10 @@ impl ComposeScene {
     pub fn new(runtime: Runtime, density: Density, invalidate: Box<dyn FnMut() + 'static>) -> Self {
         Self {
             coroutine_scope: runtime,
+            coroutine_tasks: Rc::new(LocalSet::new()),
+            dispatched_tasks: DispatchedTasks::new(),
+            frame_recorder: Rc::new(RefCell::new(FrameRecorder::new())),
+            frame_stats: FrameStats::default(),
             density,
             invalidate,
             default_pointer_state_tracker: DefaultPointerStateTracker::new(),
@@ -87,15 +120,68 @@ impl ComposeScene {
         }
     }
 
+    /// run the launched tasks which are ready until all of them are waiting again
+    pub fn dispatch_coroutines(&mut self) {
+        self.coroutine_scope.block_on(self.coroutine_tasks.run_until(tokio::task::yield_now()));
+    }
+
+    /// a handle to post work to the UI thread of this scene from any thread
+    pub fn dispatcher(&self) -> UiDispatcher {
+        self.dispatched_tasks.dispatcher()
+    }
+
+    /// run the work posted through the dispatchers of this scene, returns whether anything ran
+    pub fn run_dispatched(&mut self) -> bool {
+        self.dispatched_tasks.run()
+    }
+
+    /// run the work posted through the dispatchers of this scene as soon as it is posted, until `timeout`
+    /// passed. the window loop passes what is left of the frame budget, so posts do not speed up frames
+    pub fn wait_for_dispatched(&mut self, timeout: Duration) -> bool {
+        self.dispatched_tasks.wait(timeout)
+    }
+
+    /// run the passes of a frame in `frame`, their time and work are counted in the stats of this scene
+    /// and not in those of another scene of the same thread. passes run outside of it are not counted
+    pub fn record_frame<R>(&self, frame: impl FnOnce() -> R) -> R {
+        frame_stats::with_recorder(&self.frame_recorder, frame)
+    }
+
+    /// end the current frame, returns the time spent and the work done since the previous one
+    pub fn finish_frame(&mut self) -> FrameStats {
+        self.frame_stats = self.frame_recorder.borrow_mut().finish_frame();
+        self.frame_stats
+    }
+
+    /// the stats of the last frame ended by `finish_frame`
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// record the passes and stats of the following frames, until `stop_trace`
+    pub fn start_trace(&mut self) {
+        self.frame_recorder.borrow_mut().start_trace();
+    }
+
+    /// write the frames recorded since `start_trace` to `path` as Chrome trace events,
+    /// the file can be opened with chrome://tracing or Perfetto
+    pub fn stop_trace(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        self.frame_recorder.borrow_mut().stop_trace(path.as_ref())
+    }
+
+    /// the density and the tasks of this scene take effect for the content the owner sets afterwards
     pub fn attach(&mut self, owner: Rc<RefCell<SkiaBaseOwner>>) {
         {
-            let owner = owner.borrow_mut();
+            let mut owner = owner.borrow_mut();
+            owner.set_density(self.density);
+            owner.set_coroutine_tasks(Some(CoroutineTasks(self.coroutine_tasks.clone())));
         }
 
         self.owners.push(owner);
     }
 
     pub fn detach(&mut self, owner: Rc<RefCell<SkiaBaseOwner>>) {
+        owner.borrow_mut().set_coroutine_tasks(None);
         self.owners.retain(|o| !Rc::ptr_eq(o, &owner));
     }
 
@@ -296,4 +382,4 @@ impl ComposeScene {
             _ => {}
         }
     }
-}
\ No newline at end of file
+}
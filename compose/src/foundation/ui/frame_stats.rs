@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+thread_local! {
+    /// the recorders of the scenes running a frame on this thread, the innermost one records the passes
+    static ACTIVE_RECORDERS: RefCell<Vec<Rc<RefCell<FrameRecorder>>>> = RefCell::new(vec![]);
+    /// the passes running on this thread, innermost last, along with the time spent in the passes they ran
+    static ACTIVE_PHASES: RefCell<Vec<(FramePhase, Duration)>> = RefCell::new(vec![]);
+}
+
+/// the time spent in each pass of a frame and the work done by it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameStats {
+    pub composition: Duration,
+    pub measure: Duration,
+    pub layout: Duration,
+    pub draw: Duration,
+    pub groups_composed: usize,
+    pub nodes_remeasured: usize,
+    pub nodes_relaid_out: usize,
+    pub nodes_drawn: usize,
+}
+
+impl FrameStats {
+    /// the time of all passes, a pass run by another one, like a subcomposition while measuring,
+    /// is only counted as its own kind
+    pub fn total(&self) -> Duration {
+        self.composition + self.measure + self.layout + self.draw
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FramePhase {
+    Composition,
+    Measure,
+    Layout,
+    Draw,
+}
+
+impl FramePhase {
+    fn name(&self) -> &'static str {
+        match self {
+            FramePhase::Composition => "composition",
+            FramePhase::Measure => "measure",
+            FramePhase::Layout => "layout",
+            FramePhase::Draw => "draw",
+        }
+    }
+}
+
+/// the stats of the frame in progress of a scene, and the trace events while tracing
+pub(crate) struct FrameRecorder {
+    stats: FrameStats,
+    origin: Instant,
+    trace: Option<Vec<Value>>,
+}
+
+impl FrameRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            stats: FrameStats::default(),
+            origin: Instant::now(),
+            trace: None,
+        }
+    }
+
+    fn timestamp(&self, instant: Instant) -> f64 {
+        instant.duration_since(self.origin).as_secs_f64() * 1_000_000.0
+    }
+
+    /// end the current frame, returns what it recorded
+    pub(crate) fn finish_frame(&mut self) -> FrameStats {
+        let stats = std::mem::take(&mut self.stats);
+
+        let timestamp = self.timestamp(Instant::now());
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(json!({
+                "name": "frame",
+                "cat": "compose",
+                "ph": "C",
+                "ts": timestamp,
+                "pid": 0,
+                "tid": 0,
+                "args": {
+                    "groups_composed": stats.groups_composed,
+                    "nodes_remeasured": stats.nodes_remeasured,
+                    "nodes_relaid_out": stats.nodes_relaid_out,
+                    "nodes_drawn": stats.nodes_drawn,
+                },
+            }));
+        }
+        stats
+    }
+
+    pub(crate) fn start_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// stop tracing and write the events recorded since `start_trace` to `path` in the Chrome trace event format
+    pub(crate) fn stop_trace(&mut self, path: &Path) -> std::io::Result<()> {
+        let events = self.trace.take().unwrap_or_default();
+        let trace = json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms",
+        });
+
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(trace.to_string().as_bytes())
+    }
+}
+
+/// pops the recorder pushed by `with_recorder`, even if the frame panics
+struct ActiveRecorderGuard;
+
+impl Drop for ActiveRecorderGuard {
+    fn drop(&mut self) {
+        ACTIVE_RECORDERS.with(|recorders| {
+            recorders.borrow_mut().pop();
+        });
+    }
+}
+
+/// run `block` recording its passes and work into `recorder`
+pub(crate) fn with_recorder<R>(recorder: &Rc<RefCell<FrameRecorder>>, block: impl FnOnce() -> R) -> R {
+    ACTIVE_RECORDERS.with(|recorders| recorders.borrow_mut().push(recorder.clone()));
+    let _guard = ActiveRecorderGuard;
+    block()
+}
+
+fn with_active_recorder(block: impl FnOnce(&mut FrameRecorder)) {
+    let recorder = ACTIVE_RECORDERS.with(|recorders| recorders.borrow().last().cloned());
+    if let Some(recorder) = recorder {
+        block(&mut recorder.borrow_mut());
+    }
+}
+
+/// pops the pass pushed by `record_phase`, even if it panics, returns the time of the passes it ran
+struct ActivePhaseGuard;
+
+impl ActivePhaseGuard {
+    fn finish(self) -> Duration {
+        let nested = ACTIVE_PHASES.with(|phases| phases.borrow().last().map(|(_, nested)| *nested));
+        nested.unwrap_or_default()
+    }
+}
+
+impl Drop for ActivePhaseGuard {
+    fn drop(&mut self) {
+        ACTIVE_PHASES.with(|phases| {
+            phases.borrow_mut().pop();
+        });
+    }
+}
+
+/// run `block` as the `phase` pass of the current frame. a pass of the same kind as the one running is part
+/// of it, the time of a pass of another kind is taken out of the running one
+pub(crate) fn record_phase<R>(phase: FramePhase, block: impl FnOnce() -> R) -> R {
+    let running = ACTIVE_PHASES.with(|phases| phases.borrow().last().map(|(running, _)| *running));
+    if running == Some(phase) {
+        return block();
+    }
+
+    let start = Instant::now();
+    ACTIVE_PHASES.with(|phases| phases.borrow_mut().push((phase, Duration::ZERO)));
+    let guard = ActivePhaseGuard;
+    let result = block();
+    let nested = guard.finish();
+    let elapsed = start.elapsed();
+
+    ACTIVE_PHASES.with(|phases| {
+        if let Some((_, running_nested)) = phases.borrow_mut().last_mut() {
+            *running_nested += elapsed;
+        }
+    });
+
+    with_active_recorder(|recorder| {
+        let stats = &mut recorder.stats;
+        let own = elapsed.saturating_sub(nested);
+        match phase {
+            FramePhase::Composition => stats.composition += own,
+            FramePhase::Measure => stats.measure += own,
+            FramePhase::Layout => stats.layout += own,
+            FramePhase::Draw => stats.draw += own,
+        }
+
+        let timestamp = recorder.timestamp(start);
+        if let Some(trace) = recorder.trace.as_mut() {
+            trace.push(json!({
+                "name": phase.name(),
+                "cat": "compose",
+                "ph": "X",
+                "ts": timestamp,
+                "dur": elapsed.as_secs_f64() * 1_000_000.0,
+                "pid": 0,
+                "tid": 0,
+            }));
+        }
+    });
+    result
+}
+
+/// count work done by the current frame
+pub(crate) fn record(block: impl FnOnce(&mut FrameStats)) {
+    with_active_recorder(|recorder| block(&mut recorder.stats));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use crate::foundation::bridge::skia_base_owner::SkiaBaseOwner;
+    use crate::foundation::composer::Composer;
+    use crate::foundation::geometry::{Density, IntRect};
+    use crate::foundation::measure_layout_defer_action_manager::MeasureLayoutDeferActionManager;
+    use crate::foundation::modifier::Modifier;
+    use crate::foundation::ui::compose_scene::ComposeScene;
+    use crate::foundation::ui::frame_stats::{record_phase, with_recorder, FramePhase, FrameRecorder};
+    use crate::widgets::r#box::BoxLayout;
+
+    fn scene() -> ComposeScene {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        ComposeScene::new(runtime, Density::default(), Box::new(|| {}))
+    }
+
+    fn compose_and_measure(owner: &Rc<RefCell<SkiaBaseOwner>>) {
+        owner.borrow().set_content(|| {
+            BoxLayout(Modifier, |_| {
+                BoxLayout(Modifier, |_| {});
+            });
+        });
+        Composer::apply_changes();
+        MeasureLayoutDeferActionManager::with_manager(|defer_measure, defer_layout| {
+            owner.borrow_mut().dispatch_measure(100, 100);
+            defer_measure();
+            owner.borrow_mut().dispatch_layout();
+            defer_layout();
+        });
+    }
+
+    #[test]
+    fn scenes_of_one_thread_keep_their_own_stats() {
+        let mut first = scene();
+        let mut second = scene();
+        first.start_trace();
+
+        let owner = SkiaBaseOwner::new(IntRect::new(0, 0, 100, 100));
+        first.attach(owner.clone());
+        first.record_frame(|| compose_and_measure(&owner));
+        second.record_frame(|| {});
+
+        let stats = first.finish_frame();
+        assert!(stats.groups_composed > 0, "{stats:?}");
+        assert!(stats.nodes_remeasured >= 2, "{stats:?}");
+        assert!(stats.nodes_relaid_out >= 2, "{stats:?}");
+        assert!(stats.measure > Duration::ZERO && stats.layout > Duration::ZERO, "{stats:?}");
+        assert_eq!(first.frame_stats(), stats);
+        assert_eq!(first.finish_frame().groups_composed, 0);
+        assert_eq!(second.finish_frame(), Default::default());
+
+        let path = std::env::temp_dir().join("compose_frame_stats_test.json");
+        first.stop_trace(&path).unwrap();
+        let trace: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert!(events.iter().any(|event| event["name"] == "measure" && event["ph"] == "X"));
+        assert!(events.iter().any(|event| event["name"] == "layout" && event["ph"] == "X"));
+        assert!(events.iter().any(|event| event["name"] == "frame" && event["ph"] == "C"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn nested_passes_count_as_their_own_kind() {
+        let recorder = Rc::new(RefCell::new(FrameRecorder::new()));
+        with_recorder(&recorder, || {
+            record_phase(FramePhase::Layout, || {
+                std::thread::sleep(Duration::from_millis(5));
+                record_phase(FramePhase::Measure, || {
+                    record_phase(FramePhase::Measure, || std::thread::sleep(Duration::from_millis(20)));
+                });
+            });
+        });
+
+        let stats = recorder.borrow_mut().finish_frame();
+        assert!(stats.measure >= Duration::from_millis(20), "{stats:?}");
+        assert!(stats.layout >= Duration::from_millis(5) && stats.layout < Duration::from_millis(20), "{stats:?}");
+    }
+}
@@ -5,5 +5,7 @@ pub mod arrangement;
 pub(crate) mod size_mode;
 pub mod input;
 pub mod compose_scene;
+pub mod ui_dispatcher;
+pub mod frame_stats;
 pub(crate) mod hit_test_result;
-pub(crate) mod platform;
\ No newline at end of file
+pub mod platform;
\ No newline at end of file
@@ -0,0 +1,11 @@
+use crate::foundation::composition_local::{static_composition_local_of, CompositionLocal};
+use crate::foundation::geometry::Density;
+use crate::foundation::layout_direction::LayoutDirection;
+use crate::foundation::ui::platform::view_configuration::ViewConfiguration;
+
+/// provided by the owner of the composition
+pub static LOCAL_DENSITY: CompositionLocal<Density> = static_composition_local_of(Density::default);
+
+pub static LOCAL_LAYOUT_DIRECTION: CompositionLocal<LayoutDirection> = static_composition_local_of(LayoutDirection::default);
+
+pub static LOCAL_VIEW_CONFIGURATION: CompositionLocal<ViewConfiguration> = static_composition_local_of(ViewConfiguration::default);
@@ -1 +1,2 @@
-pub(crate) mod view_configuration;
\ No newline at end of file
+pub mod view_configuration;
+pub mod composition_locals;
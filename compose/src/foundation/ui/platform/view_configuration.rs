@@ -1,8 +1,8 @@
 use crate::foundation::geometry::dp_size::DpSize;
 use crate::foundation::geometry::IntoDp;
 
-#[derive(Debug, Copy, Clone)]
-pub(crate) struct ViewConfiguration {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewConfiguration {
     pub minimumTouchTargetSize: DpSize
 }
 
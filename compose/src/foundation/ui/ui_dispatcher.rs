@@ -0,0 +1,190 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+use crate::foundation::mutable_state::MutableState;
+
+type DispatchedTask = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    /// the states written through a `StateSetter`, by the id of the setter
+    static SHARED_STATES: RefCell<HashMap<usize, Rc<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+static NEXT_SHARED_STATE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A handle to post work to the UI thread of a `ComposeScene` from any thread. The posted tasks run
+/// on the UI thread before the next frame, in the order they were posted.
+#[derive(Clone)]
+pub struct UiDispatcher {
+    sender: Sender<DispatchedTask>,
+    thread: ThreadId,
+}
+
+impl UiDispatcher {
+    /// run `task` on the UI thread, returns false if the scene is gone
+    pub fn post(&self, task: impl FnOnce() + Send + 'static) -> bool {
+        self.sender.send(Box::new(task)).is_ok()
+    }
+
+    /// a handle to write `state` from any thread, it has to be created on the UI thread. the state is
+    /// registered with the thread it is created on, which is where the posted writes look it up
+    pub fn state_setter<T: Send + 'static>(&self, state: &MutableState<T>) -> StateSetter<T> {
+        assert_eq!(std::thread::current().id(), self.thread, "a state setter has to be created on the UI thread of its scene");
+        let id = NEXT_SHARED_STATE_ID.fetch_add(1, Ordering::Relaxed);
+        SHARED_STATES.with(|states| states.borrow_mut().insert(id, Rc::new(state.clone())));
+
+        StateSetter {
+            handle: Arc::new(SharedStateHandle { id, dispatcher: self.clone() }),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// the registration of a shared state, removed on the UI thread once the last setter is dropped
+struct SharedStateHandle {
+    id: usize,
+    dispatcher: UiDispatcher,
+}
+
+impl Drop for SharedStateHandle {
+    fn drop(&mut self) {
+        let id = self.id;
+        self.dispatcher.post(move || {
+            SHARED_STATES.with(|states| states.borrow_mut().remove(&id));
+        });
+    }
+}
+
+/// A `Send` handle to a `MutableState` living on the UI thread, writes are posted to the UI thread
+/// and picked up by the recomposer like any other write.
+pub struct StateSetter<T> {
+    handle: Arc<SharedStateHandle>,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Clone for StateSetter<T> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Send + 'static> StateSetter<T> {
+    /// write `value` into the state on the UI thread, returns false if the scene is gone
+    pub fn set(&self, value: T) -> bool {
+        self.with_state(move |state| state.set(value))
+    }
+
+    /// modify the value of the state on the UI thread, returns false if the scene is gone
+    pub fn update(&self, block: impl FnOnce(&mut T) + Send + 'static) -> bool where T: Clone {
+        self.with_state(move |state| state.update(block))
+    }
+
+    fn with_state(&self, block: impl FnOnce(&MutableState<T>) + Send + 'static) -> bool {
+        // the handle is moved into the task so the state stays registered until the task ran
+        let handle = self.handle.clone();
+        self.handle.dispatcher.post(move || {
+            let state = SHARED_STATES.with(|states| states.borrow().get(&handle.id).cloned());
+            if let Some(state) = state.and_then(|state| state.downcast::<MutableState<T>>().ok()) {
+                block(&state);
+            }
+        })
+    }
+}
+
+/// the receiving end of the dispatchers of a scene
+pub(crate) struct DispatchedTasks {
+    sender: Sender<DispatchedTask>,
+    receiver: Receiver<DispatchedTask>,
+    thread: ThreadId,
+}
+
+impl DispatchedTasks {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver, thread: std::thread::current().id() }
+    }
+
+    pub(crate) fn dispatcher(&self) -> UiDispatcher {
+        UiDispatcher { sender: self.sender.clone(), thread: self.thread }
+    }
+
+    /// run the tasks posted so far, returns whether any task ran
+    pub(crate) fn run(&self) -> bool {
+        let mut ran = false;
+        while let Ok(task) = self.receiver.try_recv() {
+            task();
+            ran = true;
+        }
+        ran
+    }
+
+    /// run the tasks as they are posted until `timeout` passed, returns whether any task ran
+    pub(crate) fn wait(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut ran = false;
+        loop {
+            match self.receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(task) => {
+                    task();
+                    ran = true;
+                }
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return ran,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::foundation::mutable_state::mutable_state_of;
+    use crate::foundation::ui::ui_dispatcher::DispatchedTasks;
+
+    #[test]
+    fn wait_runs_the_posted_tasks_for_the_whole_timeout() {
+        let tasks = DispatchedTasks::new();
+        let dispatcher = tasks.dispatcher();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let poster = std::thread::spawn(move || {
+            for value in 0..3 {
+                let sender = sender.clone();
+                dispatcher.post(move || sender.send(value).unwrap());
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+
+        let start = Instant::now();
+        assert!(tasks.wait(Duration::from_millis(100)));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        poster.join().unwrap();
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        let start = Instant::now();
+        assert!(!tasks.wait(Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn state_setter_is_created_on_the_ui_thread() {
+        let tasks = DispatchedTasks::new();
+        let dispatcher = tasks.dispatcher();
+        let setter = dispatcher.state_setter(&mutable_state_of(0));
+        drop(setter);
+
+        let created = std::thread::spawn(move || {
+            dispatcher.state_setter(&mutable_state_of(0));
+        }).join();
+        assert!(created.is_err());
+    }
+}
@@ -5,7 +5,6 @@ use std::rc::Rc;
 use crate::foundation::applier::{AbstractApplier, Applier};
 use crate::foundation::layout_node::LayoutNode;
 use crate::foundation::utils::box_wrapper::WrapWithBox;
-use crate::foundation::utils::option_extension::OptionThen;
 
 pub(crate) struct UiApplier {
     applier_impl: AbstractApplier<Rc<RefCell<LayoutNode>>>,
@@ -33,9 +32,13 @@ impl Applier<Rc<RefCell<LayoutNode>>> for UiApplier {
     }
 
     fn on_end_changes(&self) {
-        self.root.borrow_mut().owner.as_ref().and_then(|owner| {
-            owner.upgrade()
-        }).then(|owner| owner.borrow_mut().on_end_apply_changes());
+        let owner = self.root.borrow().owner.as_ref().and_then(|owner| owner.upgrade());
+        // a subcomposition applies its changes while the owner is busy measuring
+        if let Some(owner) = owner {
+            if let Ok(mut owner) = owner.try_borrow_mut() {
+                owner.on_end_apply_changes();
+            }
+        }
     }
 
     fn down(&mut self, node: Rc<RefCell<LayoutNode>>) {
@@ -54,6 +57,7 @@ impl Applier<Rc<RefCell<LayoutNode>>> for UiApplier {
     fn insert_top_down(&self, index: usize, instance: Rc<RefCell<LayoutNode>>) {}
 
     fn insert_bottom_up(&self, index: usize, instance: Rc<RefCell<LayoutNode>>) {
+        LayoutNode::remove_from_parent(&instance);
         self.get_current().borrow_mut().insert_at(index, instance)
     }
 
@@ -62,6 +66,6 @@ impl Applier<Rc<RefCell<LayoutNode>>> for UiApplier {
     }
 
     fn r#move(&self, from: usize, to: usize, count: usize) {
-        todo!()
+        self.get_current().borrow_mut().move_at(from, to, count)
     }
 }
\ No newline at end of file
@@ -1,20 +1,32 @@
+use std::marker::PhantomData;
+
 use crate::foundation::composer_impl::ComposerImpl;
-use crate::foundation::layout_node::LayoutNode;
 
-pub(crate) struct Updater<'a> {
+/// sets up and updates the node of type `T` emitted by a `ComposeNode`
+pub struct Updater<'a, T> {
     composer: &'a mut ComposerImpl,
+    _marker: PhantomData<fn(&mut T)>,
 }
 
-impl<'a> Updater<'a> {
-    pub fn new(composer: &'a mut ComposerImpl) -> Self {
+impl<'a, T: 'static> Updater<'a, T> {
+    pub(crate) fn new(composer: &'a mut ComposerImpl) -> Self {
         Self {
-            composer
+            composer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `block` is applied to a new node, and to a reused node which is composed for other content
+    pub fn set<V: 'static>(&mut self, value: V, block: impl FnOnce(&mut T, V) + 'static) {
+        if self.composer.inserting || self.composer.reusing() {
+            self.composer.apply(value, block)
         }
     }
 
-    pub fn set<V: 'static>(&mut self, value: V, block: impl FnOnce(&mut LayoutNode, V) + 'static) {
-        if self.composer.inserting {
+    /// like `set`, `block` is applied again whenever `value` differs from the last composition
+    pub fn update<V: PartialEq + Clone + 'static>(&mut self, value: V, block: impl FnOnce(&mut T, V) + 'static) {
+        if self.composer.changed(&value) {
             self.composer.apply(value, block)
         }
     }
-}
\ No newline at end of file
+}
@@ -123,7 +123,7 @@ fn place_in_box(placeable: &mut dyn Placeable,
     placeable.place_at(position, placeable.get_size(), 0.0, None);
 }
 
-fn remember_box_measure_policy(alignment: Alignment, propagate_min_constraint: bool) -> MultiChildrenMeasurePolicy {
+pub(crate) fn remember_box_measure_policy(alignment: Alignment, propagate_min_constraint: bool) -> MultiChildrenMeasurePolicy {
     MultiChildrenMeasurePolicyDelegate(move |measure_scope: &dyn MeasureScope,
                                              measurables: &mut [&mut dyn Measurable],
                                              constraints: &Constraints| {
@@ -205,8 +205,8 @@ fn remember_box_measure_policy(alignment: Alignment, propagate_min_constraint: b
                 }).collect::<Vec<Alignment>>();
 
                 measure_scope.layout((box_width, box_height), (move |scope| {
-                    placeables.iter_mut().enumerate().for_each(|(index, placeable)| {
-                        let mut placeable = placeable.as_mut().unwrap().borrow_mut();
+                    placeables.iter().enumerate().for_each(|(index, placeable)| {
+                        let mut placeable = placeable.as_ref().unwrap().borrow_mut();
 
                         place_in_box(placeable.deref_mut(),
                                      layout_direction,
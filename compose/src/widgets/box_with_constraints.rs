@@ -0,0 +1,72 @@
+use std::rc::Rc;
+
+use compose_macro::Composable;
+
+use crate as compose;
+use crate::foundation::constraint::Constraints;
+use crate::foundation::geometry::{Density, Dp};
+use crate::foundation::measure_scope::{MeasureScope, MeasureScopeLayoutAction};
+use crate::foundation::modifier::Modifier;
+use crate::foundation::placement_scope::PlacementScope;
+use crate::foundation::subcompose_layout::{SubcomposeLayout, SubcomposeMeasurePolicyDelegate};
+use crate::widgets::r#box::BoxScope;
+
+/// the scope of the content of a `BoxWithConstraints`, exposing the constraints it is measured with
+pub trait BoxWithConstraintsScope: BoxScope {
+    fn constraints(&self) -> Constraints;
+
+    fn min_width(&self) -> Dp;
+    fn max_width(&self) -> Dp;
+    fn min_height(&self) -> Dp;
+    fn max_height(&self) -> Dp;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BoxWithConstraintsScopeImpl {
+    density: Density,
+    constraints: Constraints,
+}
+
+impl BoxScope for BoxWithConstraintsScopeImpl {}
+
+impl BoxWithConstraintsScope for BoxWithConstraintsScopeImpl {
+    fn constraints(&self) -> Constraints {
+        self.constraints
+    }
+
+    fn min_width(&self) -> Dp {
+        self.density.px_to_dp(self.constraints.min_width)
+    }
+
+    fn max_width(&self) -> Dp {
+        self.density.px_to_dp(self.constraints.max_width)
+    }
+
+    fn min_height(&self) -> Dp {
+        self.density.px_to_dp(self.constraints.min_height)
+    }
+
+    fn max_height(&self) -> Dp {
+        self.density.px_to_dp(self.constraints.max_height)
+    }
+}
+
+/// A `BoxLayout` whose content is composed once the constraints it is measured with are known, so
+/// it can pick what to show depending on the available space.
+#[Composable]
+pub fn BoxWithConstraints(modifier: Modifier, content: impl Fn(&dyn BoxWithConstraintsScope) + 'static) {
+    let content = Rc::new(content);
+
+    SubcomposeLayout(modifier, SubcomposeMeasurePolicyDelegate(move |scope, constraints| {
+        let box_scope = BoxWithConstraintsScopeImpl {
+            density: scope.get_density(),
+            constraints: *constraints,
+        };
+
+        let content = content.clone();
+        let measurable = scope.subcompose((), move || content(&box_scope));
+        let (size, placeable) = measurable.borrow_mut().measure(constraints);
+
+        scope.layout(size, move |scope: &dyn PlacementScope| scope.place_relative(&placeable, 0, 0))
+    }));
+}
@@ -2,9 +2,11 @@ use std::rc::Rc;
 use compose_macro::Composable;
 
 use crate as compose;
-use crate::foundation::compose_node::ComposeNode;
+use crate::foundation::compose_node::ReusableComposeNode;
+use crate::foundation::layout_node::LayoutNode;
 use crate::foundation::measurable::MultiChildrenMeasurePolicy;
 use crate::foundation::modifier::Modifier;
+use crate::foundation::ui::platform::composition_locals::{LOCAL_DENSITY, LOCAL_LAYOUT_DIRECTION, LOCAL_VIEW_CONFIGURATION};
 
 #[Composable]
 pub fn Layout(
@@ -13,9 +15,22 @@ pub fn Layout(
     content: impl FnMut(),
 ) {
     let materialzed = modifier.materialize();
+    let density = LOCAL_DENSITY.current();
+    let layout_direction = LOCAL_LAYOUT_DIRECTION.current();
+    let view_configuration = LOCAL_VIEW_CONFIGURATION.current();
 
-    ComposeNode(
+    ReusableComposeNode(
+        LayoutNode::new,
         move |updater| {
+            updater.update(density, |node, density| {
+                node.set_density(density);
+            });
+            updater.update(layout_direction, |node, layout_direction| {
+                node.set_layout_direction(layout_direction);
+            });
+            updater.update(view_configuration, |node, view_configuration| {
+                node.set_view_configuration(view_configuration);
+            });
             updater.set(measure_policy, |node, measure_policy| {
                 node.set_measure_policy(measure_policy);
             });
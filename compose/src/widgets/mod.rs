@@ -1,4 +1,5 @@
 pub mod r#box;
+pub mod box_with_constraints;
 pub mod layout;
 pub mod column;
 pub mod cross_axis_alignment;
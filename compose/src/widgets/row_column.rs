@@ -97,7 +97,7 @@ pub(crate) fn row_column_measure_policy(
 
         let layout_direction = measure_scope.get_layout_direction();
         measure_scope.layout((layout_width, layout_height), move |placement_scope| {
-            row_column_measurement_helper.place_helper(placement_scope, measure_result, 0, layout_direction, placeables, parent_data)
+            row_column_measurement_helper.place_helper(placement_scope, &measure_result, 0, layout_direction, &placeables, &parent_data)
         })
     })
 }
@@ -322,11 +322,11 @@ impl RowColumnMeasureHelper {
 
     pub fn place_helper(&self,
                         placement_scope: &dyn PlacementScope,
-                        measure_result: RowColumnMeasureHelperResult,
+                        measure_result: &RowColumnMeasureHelperResult,
                         cross_axis_offset: i32,
                         layout_direction: LayoutDirection,
-                        placeables: Vec<Option<Rc<RefCell<dyn Placeable>>>>,
-                        parent_data: Vec<Option<RowColumnParentData>>,
+                        placeables: &[Option<Rc<RefCell<dyn Placeable>>>],
+                        parent_data: &[Option<RowColumnParentData>],
     ) {
         let main_axis_positions = &measure_result.main_axis_positions;
 
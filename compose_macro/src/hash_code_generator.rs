@@ -1,25 +1,17 @@
-use std::collections::HashSet;
-use std::sync::RwLock;
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
 
-use lazy_static::lazy_static;
-use rand::random;
+/// the group key of a composable, derived from where it is declared so that it is the same on every build.
+/// the module path tells apart composables declared at the same location of files with the same path in
+/// different crates, it is only known to the expanded code so the key is hashed in a const there
+pub(crate) fn generate_hash_code(function_name: &Ident) -> TokenStream {
+    let span = function_name.span().unwrap();
+    let source_location = format!("{}:{}:{}:{}", span.file(), span.line(), span.column(), function_name);
 
-lazy_static! {
-    static ref COMPOSER_HASH: RwLock<HashSet<u64>> = RwLock::new(HashSet::new());
-}
-
-pub(crate) fn generate_hash_code() -> u64 {
-    let mut hash: u64 = random();
-
-    {
-        let mut composer_hash = COMPOSER_HASH.write().unwrap();
-
-        // hash < 1000 reserve for Composer
-        while hash < 1000 && composer_hash.contains(&hash) {
-            hash = random();
+    quote! {
+        {
+            const __GROUP_KEY: u64 = compose::foundation::key::composable_key(module_path!(), #source_location);
+            __GROUP_KEY
         }
-        composer_hash.insert(hash.clone());
     }
-
-    return hash;
 }
@@ -4,7 +4,7 @@ use proc_macro::{Span, TokenStream};
 
 use proc_macro2::Ident;
 use quote::quote;
-use syn::ItemFn;
+use syn::{FnArg, ItemFn, Pat};
 use syn::parse_macro_input;
 use syn::ReturnType;
 use syn::spanned::Spanned;
@@ -12,7 +12,7 @@ use syn::spanned::Spanned;
 use crate::attribute_parser::parse_attribute;
 use crate::function_params_collector::collect_function_params;
 use crate::hash_code_generator::generate_hash_code;
-use crate::signature_checker::verify_signature;
+use crate::signature_checker::{is_comparable_type, is_restartable, verify_signature};
 
 mod attribute_parser;
 mod function_params_collector;
@@ -50,44 +50,129 @@ pub fn Composable(attribute: TokenStream, function: TokenStream) -> TokenStream
     );
     let function_body = function.block.as_ref();
 
-    let hash = generate_hash_code();
+    let hash = generate_hash_code(origin_function_name);
+    let source_name = origin_function_name.to_string();
 
     let function_sig = &function.sig;
     let function_generics = &function.sig.generics;
     let where_calause = function_generics.where_clause.as_ref();
     let return_type = &function.sig.output;
 
+    let restartable = is_restartable(signature);
+
+    // params are cloned into the restart block, the block is only registered if all of them can be cloned
+    let (capture_restart_params, update_restart_scope) = if function_inputs.is_empty() {
+        (quote! {}, quote! {
+            compose::foundation::composer::ScopeUpdateScope::update_scope(
+                &mut *__scope.borrow_mut(),
+                Box::new(move || #function_name()),
+            );
+        })
+    } else {
+        (quote! {
+            let __restart_params = {
+                use compose::foundation::composer::{RestartParamClone as _, RestartParamFallback as _};
+                (#((&compose::foundation::composer::RestartParam(&#function_inputs)).restart_param(),)*)
+            };
+        }, quote! {
+            if let (#(Some(#function_inputs),)*) = __restart_params {
+                compose::foundation::composer::ScopeUpdateScope::update_scope(
+                    &mut *__scope.borrow_mut(),
+                    Box::new(move || #function_name(#(#function_inputs()),*)),
+                );
+            }
+        })
+    };
+
+    // every param which can be compared is stored in the slot table, the body is skipped
+    // if none of them changed and the scope was not invalidated. borrowed params are always changed
+    let param_changes = function_inputs_with_type.iter().filter_map(|arg| match arg {
+        FnArg::Typed(pat) => match pat.pat.as_ref() {
+            Pat::Ident(ident) if is_comparable_type(&pat.ty) => {
+                let ident = &ident.ident;
+                Some(quote! { (&compose::foundation::composer::ChangedParam(&#ident)).changed() })
+            }
+            Pat::Ident(_) => Some(quote! { true }),
+            _ => None,
+        },
+        FnArg::Receiver(_) => None,
+    });
+    let compare_params = quote! {
+        let __changed = {
+            use compose::foundation::composer::{ChangedParamCompare as _, ChangedParamFallback as _};
+            false #(| #param_changes)*
+        };
+    };
+
     let start_group_stat = match return_type {
+        ReturnType::Default if restartable => {
+            quote! {
+                compose::foundation::composer::Composer::start_restart_group(#hash, #source_name);
+                #compare_params
+                #capture_restart_params
+            }
+        }
         ReturnType::Default => {
             quote! {
-                 compose::foundation::composer::Composer::start_group(#hash);
+                compose::foundation::composer::Composer::start_restart_group(#hash, #source_name);
+                #compare_params
             }
         }
+        // the caller needs the value, so the body runs in a plain group which is never skipped. the group
+        // is keyed by the call site as well, so that every call keeps the values it remembered
         _ => {
             quote! {
-                compile_error!("Composable should not return value")
+                compose::foundation::composer::Composer::start_call_site_group(#hash, #source_name);
             }
         }
     };
 
     let end_group_stat = match return_type {
+        ReturnType::Default if restartable => {
+            quote! {
+                if let Some(__scope) = compose::foundation::composer::Composer::end_restart_group() {
+                    #update_restart_scope
+                }
+            }
+        }
         ReturnType::Default => {
             quote! {
-                // compose::foundation::composer::Composer::end_restart_group();
-                compose::foundation::composer::Composer::end_group(#hash);
+                compose::foundation::composer::Composer::end_restart_group();
             }
         }
         _ => {
             quote! {
-                compile_error!("Composable should not return value")
+                compose::foundation::composer::Composer::end_group(#hash);
+                __result
             }
         }
     };
 
+    // the body runs in a closure, so that a `return` or `?` in it still reaches the end of the group
+    let function_body = match return_type {
+        ReturnType::Default => quote! {
+            if !__changed && compose::foundation::composer::Composer::skipping() {
+                compose::foundation::composer::Composer::skip_to_end();
+            } else {
+                (|| #function_body)();
+            }
+        },
+        ReturnType::Type(_, return_type) => quote! {
+            let __result = (|| -> #return_type #function_body)();
+        },
+    };
+
+    let track_caller = match return_type {
+        ReturnType::Default => quote! {},
+        _ => quote! { #[track_caller] },
+    };
+
     let wrapped_function = if !function_inputs.is_empty() {
         (quote! {
                 #[inline]
-                #function_visibility fn #function_name #function_generics(#function_inputs_with_type) #where_calause {
+                #track_caller
+                #[allow(clippy::redundant_closure_call)]
+                #function_visibility fn #function_name #function_generics(#function_inputs_with_type) #return_type #where_calause {
                     #start_group_stat
                     #function_body
                     #end_group_stat
@@ -96,12 +181,9 @@ pub fn Composable(attribute: TokenStream, function: TokenStream) -> TokenStream
     } else {
         (quote! {
                 #[inline]
-                #function_visibility fn #function_name #function_generics() #where_calause {
-                    if compose::foundation::composer::Composer::skipping() {
-                        compose::foundation::composer::Composer::skip_to_end();
-                        return;
-                    }
-
+                #track_caller
+                #[allow(clippy::redundant_closure_call)]
+                #function_visibility fn #function_name #function_generics() #return_type #where_calause {
                     #start_group_stat
                     #function_body
                     #end_group_stat
@@ -112,6 +194,7 @@ pub fn Composable(attribute: TokenStream, function: TokenStream) -> TokenStream
     let result = if !function_inputs.is_empty() {
         (quote! {
             #[inline]
+            #track_caller
             #function_visibility #function_sig {
                 #wrapped_function
 
@@ -121,6 +204,7 @@ pub fn Composable(attribute: TokenStream, function: TokenStream) -> TokenStream
     } else {
         (quote! {
             #[inline]
+            #track_caller
             #function_visibility #function_sig {
                 #wrapped_function
 
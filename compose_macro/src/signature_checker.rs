@@ -1,5 +1,6 @@
 use proc_macro::TokenStream;
 
+use quote::ToTokens;
 use syn::*;
 
 pub(crate) fn verify_signature(signature: &Signature) -> std::result::Result<(), TokenStream> {
@@ -25,3 +26,58 @@ pub(crate) fn verify_signature(signature: &Signature) -> std::result::Result<(),
 
     Ok(())
 }
+
+/// a composable can restart itself when every param is an owned, concrete type,
+/// whether the params can actually be cloned into the restart block is resolved by `RestartParam`
+pub(crate) fn is_restartable(signature: &Signature) -> bool {
+    if !signature.generics.params.is_empty() {
+        return false;
+    }
+
+    signature.inputs.iter().all(|arg| match arg {
+        FnArg::Typed(pat) => is_owned_type(pat.ty.to_token_stream()),
+        FnArg::Receiver(_) => false,
+    })
+}
+
+fn is_owned_type(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().all(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident != "impl",
+        proc_macro2::TokenTree::Punct(punct) => punct.as_char() != '&' && punct.as_char() != '\'',
+        proc_macro2::TokenTree::Group(group) => is_owned_type(group.stream()),
+        proc_macro2::TokenTree::Literal(_) => true,
+    })
+}
+
+/// a param is compared with its previous value only if it borrows nothing but statics. the autoref
+/// specialization of `ChangedParam` does not see lifetimes, it would pick the comparison for any reference
+/// and fail to store the borrowed value in the slot table
+pub(crate) fn is_comparable_type(ty: &Type) -> bool {
+    !borrows_non_static(ty.to_token_stream())
+}
+
+fn borrows_non_static(tokens: proc_macro2::TokenStream) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            // a reference with an elided lifetime
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '&' => {
+                if !matches!(tokens.peek(), Some(proc_macro2::TokenTree::Punct(punct)) if punct.as_char() == '\'') {
+                    return true;
+                }
+            }
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                if !matches!(tokens.next(), Some(proc_macro2::TokenTree::Ident(ident)) if ident == "static") {
+                    return true;
+                }
+            }
+            proc_macro2::TokenTree::Group(group) => {
+                if borrows_non_static(group.stream()) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}